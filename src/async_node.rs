@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicI64;
+
+use bytebuffer::ByteBuffer;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::encode::{Value, VoltError};
+use crate::node::{check_version, ConnInfo, login_message, next_handle, NodeOpt, parse_login_response, PING_HANDLE, response_size};
+use crate::procedure_invocation::{check_params, new_procedure_invocation};
+use crate::response::VoltResponseInfo;
+use crate::table::{new_volt_table, VoltTable};
+use crate::volt_param;
+
+/// Senders of the calls waiting for a response , `None` once the connection is gone.
+type Pending = Arc<Mutex<Option<HashMap<i64, oneshot::Sender<Result<VoltTable, VoltError>>>>>>;
+
+/// Calls encoded and waiting for the writer task , sending more waits until there is room.
+const WRITE_QUEUE: usize = 128;
+
+/// What the writer task is asked to do.
+enum Outgoing {
+    Frame(Vec<u8>),
+    Shutdown(oneshot::Sender<std::io::Result<()>>),
+}
+
+/// Forgets the pending call when its future is dropped before the response came.
+struct PendingGuard<'a> {
+    requests: &'a Pending,
+    handle: i64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut requests) = self.requests.lock() {
+            if let Some(requests) = requests.as_mut() {
+                requests.remove(&self.handle);
+            }
+        }
+    }
+}
+
+/// Connection to one server for use inside a tokio runtime.
+/// Speaks the same protocol as `Node`, but responses are awaited instead of received from a channel.
+/// The socket timeouts of `NodeOpt` are not used apart from `login_timeout` , wrap the futures in `tokio::time::timeout` instead.
+/// That is safe , a task of its own writes every call out whole , even when the future of the call is dropped.
+pub struct AsyncNode {
+    writer: mpsc::Sender<Outgoing>,
+    requests: Pending,
+    counter: AtomicI64,
+    info: ConnInfo,
+    listener: JoinHandle<()>,
+}
+
+impl Drop for AsyncNode {
+    fn drop(&mut self) {
+        self.listener.abort();
+        // the writer stops by itself once the calls queued are written , as the sender is dropped
+    }
+}
+
+impl AsyncNode {
+    /// Connect and log in, then spawn the task which hands out the responses.
+    pub async fn new(opt: NodeOpt) -> Result<AsyncNode, VoltError> {
+        if opt.tls.is_some() {
+            return Err(VoltError::Tls("not supported by AsyncNode".to_owned()));
+        }
+        let addr = format!("{}:{}", opt.ip_port.ip_host, opt.ip_port.port);
+        let mut stream = match opt.connect_timeout {
+            None => TcpStream::connect(addr).await?,
+            Some(timeout) => tokio::time::timeout(timeout, TcpStream::connect(addr)).await
+                .map_err(|_| VoltError::ConnectTimeout)??,
+        };
+        stream.set_nodelay(opt.nodelay)?;
+        let login = async {
+            stream.write_all(&login_message(&opt)).await?;
+            let len = response_size(stream.read_u32().await?, opt.max_response_size)?;
+            let mut all = vec![0; len];
+            stream.read_exact(&mut all).await?;
+            Ok::<_, VoltError>(all)
+        };
+        let all = match opt.login_timeout {
+            None => login.await?,
+            Some(timeout) => tokio::time::timeout(timeout, login).await
+                .map_err(|_| VoltError::LoginTimeout)??,
+        };
+        let info = parse_login_response(&all)?;
+        check_version(&opt, &info)?;
+
+        let (reader, writer) = stream.into_split();
+        let requests: Pending = Arc::new(Mutex::new(Some(HashMap::new())));
+        let listener = tokio::spawn(AsyncNode::listen(reader, Arc::clone(&requests), opt.max_response_size));
+        let (tx, frames) = mpsc::channel(WRITE_QUEUE);
+        tokio::spawn(AsyncNode::write(writer, frames, Arc::clone(&requests)));
+        Ok(AsyncNode {
+            writer: tx,
+            requests,
+            counter: AtomicI64::new(1),
+            info,
+            listener,
+        })
+    }
+
+    /// Handshake details of the connection.
+    pub fn conn_info(&self) -> &ConnInfo {
+        &self.info
+    }
+
+    pub fn get_sequence(&self) -> i64 {
+        next_handle(&self.counter)
+    }
+
+    /// Call a procedure , the parameters are encoded right away so the future does not borrow them.
+    pub fn call_sp(&self, query: &str, param: Vec<&dyn Value>) -> impl Future<Output=Result<VoltTable, VoltError>> + '_ {
+        let checked = check_params(&param);
+        let handle = self.get_sequence();
+        let bytes = new_procedure_invocation(handle, false, &param, query).bytes();
+        async move {
+            checked?;
+            let (tx, rx) = oneshot::channel();
+            match self.requests.lock()?.as_mut() {
+                None => return Err(VoltError::ConnectionReset),
+                Some(requests) => requests.insert(handle, tx),
+            };
+            let _guard = PendingGuard { requests: &self.requests, handle };
+            // either queued whole or not at all , so dropping the future here does not misframe the stream
+            if self.writer.send(Outgoing::Frame(bytes)).await.is_err() {
+                return Err(VoltError::ConnectionReset);
+            }
+            let mut table = rx.await.map_err(|_| VoltError::ConnectionReset)??;
+            match table.has_error() {
+                None => Ok(table),
+                Some(err) => Err(err),
+            }
+        }
+    }
+
+    pub async fn list_procedures(&self) -> Result<VoltTable, VoltError> {
+        self.call_sp("@SystemCatalog", volt_param!("PROCEDURES")).await
+    }
+
+    /// Use `@AdHoc` proc to query .
+    pub async fn query(&self, sql: &str) -> Result<VoltTable, VoltError> {
+        self.query_with_params(sql, Vec::new()).await
+    }
+
+    /// Use `@AdHoc` proc to query , binding `params` to the `?` placeholders of `sql` in order.
+    pub fn query_with_params(&self, sql: &str, params: Vec<&dyn Value>) -> impl Future<Output=Result<VoltTable, VoltError>> + '_ {
+        let mut all: Vec<&dyn Value> = Vec::with_capacity(params.len() + 1);
+        all.push(&sql);
+        all.extend(params);
+        self.call_sp("@AdHoc", all)
+    }
+
+    /// Send a `@Ping` and wait for the answer , so an `Ok` means the server is alive.
+    pub async fn ping(&self) -> Result<(), VoltError> {
+        self.call_sp("@Ping", volt_param!()).await?;
+        Ok(())
+    }
+
+    /// Close the connection , pending calls fail with `VoltError::ConnectionReset`.
+    pub async fn shutdown(&self) -> Result<(), VoltError> {
+        self.listener.abort();
+        AsyncNode::close(&self.requests);
+        let (tx, done) = oneshot::channel();
+        if self.writer.send(Outgoing::Shutdown(tx)).await.is_err() {
+            // the writer is gone with the connection
+            return Ok(());
+        }
+        match done.await {
+            Ok(res) => Ok(res?),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Write the queued calls until the connection breaks or is shut down , then fail the pending calls.
+    async fn write(mut writer: OwnedWriteHalf, mut frames: mpsc::Receiver<Outgoing>, requests: Pending) {
+        while let Some(outgoing) = frames.recv().await {
+            match outgoing {
+                Outgoing::Frame(frame) => {
+                    if writer.write_all(&frame).await.is_err() {
+                        break;
+                    }
+                }
+                Outgoing::Shutdown(done) => {
+                    let _ = done.send(writer.shutdown().await);
+                    return;
+                }
+            }
+        }
+        AsyncNode::close(&requests);
+    }
+
+    /// Read responses until the connection breaks , then fail the pending calls.
+    async fn listen(mut reader: OwnedReadHalf, requests: Pending, max_response_size: usize) {
+        while AsyncNode::job(&mut reader, &requests, max_response_size).await.is_ok() {}
+        AsyncNode::close(&requests);
+    }
+
+    /// Fail the pending calls with `VoltError::ConnectionReset` and refuse new ones.
+    fn close(requests: &Pending) {
+        let pending = match requests.lock() {
+            Ok(mut requests) => requests.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        for (_, sender) in pending.into_iter().flatten() {
+            let _ = sender.send(Err(VoltError::ConnectionReset));
+        }
+    }
+
+    async fn job(reader: &mut OwnedReadHalf, requests: &Pending, max_response_size: usize) -> Result<(), VoltError> {
+        let len = response_size(reader.read_u32().await?, max_response_size)?;
+        if len == 0 {
+            return Err(VoltError::EmptyResponse);
+        }
+        let mut all = vec![0; len];
+        reader.read_exact(&mut all).await?;
+        let mut res = ByteBuffer::from_bytes(&all);
+        let _ = res.read_u8()?;
+        let handle = res.read_i64()?;
+        if handle == PING_HANDLE {
+            return Ok(());
+        }
+        let sender = requests.lock()?.as_mut().and_then(|requests| requests.remove(&handle));
+        if let Some(sender) = sender {
+            let table = VoltResponseInfo::new(&mut res, handle)
+                .and_then(|info| new_volt_table(&mut res, info));
+            // the caller may have dropped the future already
+            let _ = sender.send(table);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use crate::encode::INT_COLUMN;
+    use crate::node::IpPort;
+
+    use super::*;
+
+    async fn read_frame(stream: &mut TcpStream) -> ByteBuffer {
+        let len = stream.read_u32().await.unwrap();
+        let mut all = vec![0; len as usize];
+        stream.read_exact(&mut all).await.unwrap();
+        ByteBuffer::from_bytes(&all)
+    }
+
+    async fn write_frame(stream: &mut TcpStream, body: ByteBuffer) {
+        let mut frame = ByteBuffer::new();
+        frame.write_u32(body.len() as u32);
+        frame.write_bytes(body.as_bytes());
+        stream.write_all(frame.as_bytes()).await.unwrap();
+    }
+
+    /// Accept one client and log it in.
+    async fn accept(listener: TcpListener) -> TcpStream {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        read_frame(&mut stream).await;
+        let mut login = ByteBuffer::new();
+        login.write_u8(0);
+        login.write_u8(0);
+        login.write_i32(1);
+        login.write_i64(2);
+        login.write_i64(0);
+        login.write_i32(0x7f000001);
+        login.write_string("test");
+        write_frame(&mut stream, login).await;
+        stream
+    }
+
+    /// Name and handle of the next call.
+    async fn read_call(stream: &mut TcpStream) -> (String, i64) {
+        let mut call = read_frame(stream).await;
+        let _version = call.read_u8().unwrap();
+        let name = call.read_string().unwrap();
+        (name, call.read_i64().unwrap())
+    }
+
+    /// Log the client in and answer one call with a single INTEGER value.
+    async fn serve(listener: TcpListener) {
+        let mut stream = accept(listener).await;
+        let (_, handle) = read_call(&mut stream).await;
+        answer(&mut stream, handle, 42).await;
+    }
+
+    /// Answer `handle` with a table of one INTEGER column `A` holding `value`.
+    async fn answer(stream: &mut TcpStream, handle: i64, value: i32) {
+        let mut res = ByteBuffer::new();
+        res.write_u8(0);
+        res.write_i64(handle);
+        res.write_u8(0);
+        res.write_i8(1);
+        res.write_i8(-128);
+        res.write_i32(0);
+        res.write_i16(1);
+        // table with column A INTEGER and one row
+        res.write_i32(0);
+        res.write_i32(0);
+        res.write_i8(0);
+        res.write_i16(1);
+        res.write_i8(INT_COLUMN);
+        res.write_string("A");
+        res.write_i32(1);
+        res.write_i32(4);
+        res.write_i32(value);
+        write_frame(stream, res).await;
+    }
+
+    #[tokio::test]
+    async fn test_call_sp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(serve(listener));
+        let opt = NodeOpt::new(IpPort::new("127.0.0.1".to_string(), port));
+        let node = AsyncNode::new(opt).await.unwrap();
+        assert_eq!(node.conn_info().build_string(), "test");
+        let mut table = node.query("select a from t").await.unwrap();
+        assert!(table.advance_row());
+        assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(42));
+        server.await.unwrap();
+        // the server hung up , so this must fail instead of waiting forever
+        match node.query("select a from t").await {
+            Err(VoltError::ConnectionReset) | Err(VoltError::Io(_)) => {}
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        // and the node stays closed
+        assert!(matches!(node.query("select a from t").await, Err(VoltError::ConnectionReset)));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_while_writing() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut stream = accept(listener).await;
+            // not reading yet , so the large call fills the socket buffers and is still being written when cancelled
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            let (name, _) = read_call(&mut stream).await;
+            assert_eq!(name, "Load");
+            let (name, handle) = read_call(&mut stream).await;
+            assert_eq!(name, "@AdHoc");
+            answer(&mut stream, handle, 7).await;
+            // until the client shuts the connection down
+            assert!(stream.read_u32().await.is_err());
+        });
+        let opt = NodeOpt::new(IpPort::new("127.0.0.1".to_string(), port));
+        let node = AsyncNode::new(opt).await.unwrap();
+        let payload = vec![1u8; 16 << 20];
+        let timeout = std::time::Duration::from_millis(50);
+        assert!(tokio::time::timeout(timeout, node.call_sp("Load", volt_param!(payload))).await.is_err());
+        assert_eq!(node.requests.lock().unwrap().as_ref().map(|r| r.len()), Some(0));
+        let mut table = node.query("select a from t").await.unwrap();
+        assert!(table.advance_row());
+        assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(7));
+        node.shutdown().await.unwrap();
+        server.await.unwrap();
+    }
+}
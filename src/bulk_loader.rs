@@ -0,0 +1,240 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::encode::{Value, VoltError};
+use crate::node::Node;
+use crate::procedure_invocation::{check_params, new_procedure_invocation};
+
+/// Called with the number of a row , counted from 0 in the order of `add_row` , and why it was not inserted.
+pub type RowFailure = Arc<dyn Fn(u64, VoltError) + Send + Sync>;
+
+/// Batches sent but not answered completely , with the condition `drain` waits on.
+struct Outstanding {
+    batches: Mutex<usize>,
+    done: Condvar,
+}
+
+struct Batch {
+    remaining: AtomicUsize,
+    outstanding: Arc<Outstanding>,
+}
+
+impl Batch {
+    fn row_done(&self) {
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let mut batches = match self.outstanding.batches.lock() {
+                Ok(batches) => batches,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            *batches -= 1;
+            self.outstanding.done.notify_all();
+        }
+    }
+}
+
+/// Counts the row as done once its callback ran , or was dropped without running.
+struct RowGuard {
+    batch: Arc<Batch>,
+}
+
+impl Drop for RowGuard {
+    fn drop(&mut self) {
+        self.batch.row_done();
+    }
+}
+
+/// Inserts many rows into one table through its default `TABLENAME.insert` procedure , like `VoltBulkLoader` of the Java client.
+/// Rows are encoded when added and sent `batch_size` at a time without waiting for the answers ,
+/// so a batch is on the wire while the next one is filled.
+/// Rows still buffered are not sent when the loader is dropped , call `drain` at the end.
+pub struct BulkLoader<'a> {
+    node: &'a Node,
+    procedure: String,
+    batch_size: usize,
+    // number , handle , number of values and encoded call of the rows not sent yet
+    buffered: Vec<(u64, i64, usize, Vec<u8>)>,
+    next_row: u64,
+    failed: Arc<AtomicU64>,
+    on_failure: Option<RowFailure>,
+    outstanding: Arc<Outstanding>,
+}
+
+impl<'a> BulkLoader<'a> {
+    /// Loader for `table` over `node` , a `batch_size` of 0 is taken as 1.
+    pub fn new(node: &'a Node, table: &str, batch_size: usize) -> Self {
+        BulkLoader {
+            node,
+            procedure: format!("{}.insert", table.to_uppercase()),
+            batch_size: batch_size.max(1),
+            buffered: vec![],
+            next_row: 0,
+            failed: Arc::new(AtomicU64::new(0)),
+            on_failure: None,
+            outstanding: Arc::new(Outstanding { batches: Mutex::new(0), done: Condvar::new() }),
+        }
+    }
+
+    /// Run `f` for every row which could not be inserted , on the listener thread of the node.
+    pub fn on_failure<F>(mut self, f: F) -> Self where F: Fn(u64, VoltError) + Send + Sync + 'static {
+        self.on_failure = Some(Arc::new(f));
+        self
+    }
+
+    /// Queue a row with one value per column of the table , sending the batch once it is full.
+    pub fn add_row(&mut self, row: Vec<&dyn Value>) -> Result<(), VoltError> {
+        check_params(&row)?;
+        let handle = self.node.get_sequence();
+        let bytes = new_procedure_invocation(handle, false, &row, &self.procedure).bytes();
+        self.buffered.push((self.next_row, handle, row.len(), bytes));
+        self.next_row += 1;
+        if self.buffered.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Send the buffered rows without waiting for their answers.
+    /// When sending breaks off , the rows left are reported as failed and the error is returned.
+    pub fn flush(&mut self) -> Result<(), VoltError> {
+        if self.buffered.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.buffered);
+        *self.outstanding.batches.lock()? += 1;
+        let batch = Arc::new(Batch {
+            remaining: AtomicUsize::new(rows.len()),
+            outstanding: Arc::clone(&self.outstanding),
+        });
+        let mut rows = rows.into_iter();
+        for (row, handle, params, bytes) in rows.by_ref() {
+            let guard = RowGuard { batch: Arc::clone(&batch) };
+            let failed = Arc::clone(&self.failed);
+            let on_failure = self.on_failure.clone();
+            let sent = self.node.send_encoded(handle, &self.procedure, params, bytes, Box::new(move |res| {
+                let _guard = guard;
+                if let Err(err) = res {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                    if let Some(on_failure) = on_failure {
+                        on_failure(row, err);
+                    }
+                }
+            }));
+            if let Err((err, answered)) = sent {
+                // the listener may have failed the row already , through its callback
+                if !answered {
+                    self.fail(row, VoltError::Other(err.to_string()));
+                }
+                for (row, _, _, _) in rows {
+                    self.fail(row, VoltError::Other(err.to_string()));
+                    batch.row_done();
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Send the buffered rows and wait until every batch is answered.
+    pub fn drain(&mut self) -> Result<(), VoltError> {
+        self.flush()?;
+        let mut batches = self.outstanding.batches.lock()?;
+        while *batches > 0 {
+            batches = self.outstanding.done.wait(batches)?;
+        }
+        Ok(())
+    }
+
+    /// Batches sent and not answered completely yet.
+    pub fn outstanding_batches(&self) -> usize {
+        match self.outstanding.batches.lock() {
+            Ok(batches) => *batches,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+
+    /// Rows added so far , sent or not.
+    pub fn rows_added(&self) -> u64 {
+        self.next_row
+    }
+
+    /// Rows which could not be inserted so far.
+    pub fn failed_rows(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    fn fail(&self, row: u64, err: VoltError) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        if let Some(on_failure) = &self.on_failure {
+            on_failure(row, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::mock_server::{answer_all, error_response, int_response, read_call, serve, try_read_call};
+    use crate::node::NodeOpt;
+    use crate::volt_param;
+
+    use super::*;
+
+    #[test]
+    fn test_bulk_loader() {
+        let ip_port = serve(|mut stream| {
+            let mut row = 0;
+            while let Some((name, handle)) = try_read_call(&mut stream) {
+                assert_eq!(name, "ORDERS.insert");
+                // the fourth row violates a constraint
+                let res = if row == 3 { error_response(handle, -2, "duplicate") } else { int_response(handle, 1) };
+                stream.write_all(&res).unwrap();
+                row += 1;
+            }
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let failures = Arc::new(Mutex::new(vec![]));
+        let seen = Arc::clone(&failures);
+        let mut loader = BulkLoader::new(&node, "orders", 4)
+            .on_failure(move |row, err| seen.lock().unwrap().push((row, err.to_string())));
+        for i in 0..10 {
+            let name = format!("order {}", i);
+            loader.add_row(volt_param!(i, name)).unwrap();
+        }
+        // two full batches went out , the last two rows wait for `drain`
+        assert_eq!(loader.rows_added(), 10);
+        loader.drain().unwrap();
+        assert_eq!(loader.outstanding_batches(), 0);
+        assert_eq!(loader.failed_rows(), 1);
+        let failures = failures.lock().unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 3);
+    }
+
+    #[test]
+    fn test_send_failure() {
+        let ip_port = serve(|mut stream| {
+            // the first row is answered only after the second was refused
+            let (_, first) = read_call(&mut stream);
+            thread::sleep(Duration::from_millis(100));
+            stream.write_all(&int_response(first, 1)).unwrap();
+            answer_all(&mut stream, 1);
+        });
+        let node = Node::new(NodeOpt { max_outstanding: Some(1), ..NodeOpt::new(ip_port) }).unwrap();
+        let failures = Arc::new(Mutex::new(vec![]));
+        let seen = Arc::clone(&failures);
+        let mut loader = BulkLoader::new(&node, "orders", 3)
+            .on_failure(move |row, err| seen.lock().unwrap().push((row, err.to_string())));
+        loader.add_row(volt_param!(1)).unwrap();
+        loader.add_row(volt_param!(2)).unwrap();
+        assert!(matches!(loader.add_row(volt_param!(3)), Err(VoltError::BackpressureExceeded)));
+        loader.drain().unwrap();
+        // the second and third row , each once and with the reason
+        assert_eq!(loader.failed_rows(), 2);
+        let failures = failures.lock().unwrap();
+        let expected = VoltError::BackpressureExceeded.to_string();
+        assert_eq!(*failures, vec![(1, expected.clone()), (2, expected)]);
+    }
+}
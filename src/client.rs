@@ -0,0 +1,522 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::{block_for_result, ConnInfo, Hashinator, Node, NodeOpt, Opts, RetryPolicy, TopologyCallback, Value, VoltError, VoltTable};
+use crate::hashinator::partition_leaders;
+use crate::volt_param;
+
+/// System procedures sent over the admin connection when there is one , as they must still work on a paused cluster.
+const ADMIN_PROCEDURES: [&str; 5] = ["@Pause", "@Resume", "@Quiesce", "@Shutdown", "@PrepareShutdown"];
+
+/// System procedures sent to the leader when it is known.
+const LEADER_PROCEDURES: [&str; 6] = ["@UpdateClasses", "@UpdateApplicationCatalog", "@Pause", "@Resume", "@Quiesce", "@Shutdown"];
+
+type TableReceiver = Receiver<Result<VoltTable, VoltError>>;
+
+/// Longest pause between two attempts to reconnect a host that is down.
+pub const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// State of one host of a `Client` , from `Client::health`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum NodeHealth {
+    /// Connected , calls are sent to it.
+    Healthy,
+    /// The connection failed , the client notices with its next call and starts reconnecting.
+    Unhealthy,
+    /// Down and being reconnected in the background , calls go to the other hosts meanwhile.
+    Reconnecting,
+}
+
+/// Opens a new connection on its own thread , pausing `reconnect_interval` before the first attempt
+/// and twice as long after every failed one , up to `MAX_RECONNECT_BACKOFF`. Stops when dropped.
+struct Reconnect {
+    connected: Receiver<Node>,
+    // dropping it wakes the thread up and makes it give up
+    _stop: Sender<()>,
+}
+
+impl Reconnect {
+    fn start(opt: NodeOpt) -> Reconnect {
+        let (tx, connected) = mpsc::channel();
+        let (_stop, stopped) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            let mut pause = opt.reconnect_interval;
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(pause) {
+                match Node::new(opt.clone()) {
+                    Ok(node) => {
+                        let _ = tx.send(node);
+                        return;
+                    }
+                    Err(err) => log_warn!("reconnecting to {}:{} failed: {}", opt.ip_port.ip_host, opt.ip_port.port, err),
+                }
+                pause = pause.saturating_mul(2).min(MAX_RECONNECT_BACKOFF);
+            }
+        });
+        Reconnect { connected, _stop }
+    }
+}
+
+/// One host of the cluster, `node` is `None` while the connection is down.
+struct ClientNode {
+    opt: NodeOpt,
+    node: Option<Node>,
+    reconnecting: Option<Reconnect>,
+}
+
+impl ClientNode {
+    /// Connect to `opt` , reconnecting in the background when that fails.
+    fn new(opt: NodeOpt) -> (ClientNode, Option<VoltError>) {
+        let (node, err) = match Node::new(opt.clone()) {
+            Ok(node) => (Some(node), None),
+            Err(err) => (None, Some(err)),
+        };
+        let mut entry = ClientNode { opt, node, reconnecting: None };
+        if entry.node.is_none() {
+            entry.mark_down();
+        }
+        (entry, err)
+    }
+
+    /// The connected node , taking over the one reconnected in the background if it is ready.
+    /// `leader_addr` is updated from the handshake of a fresh connection.
+    fn connected(&mut self, leader_addr: &mut Option<Ipv4Addr>) -> Option<&mut Node> {
+        if self.node.as_ref().is_some_and(|node| node.is_broken()) {
+            self.mark_down();
+        }
+        if let Some(reconnect) = &self.reconnecting {
+            match reconnect.connected.try_recv() {
+                Ok(node) => {
+                    *leader_addr = Some(node.conn_info().leader_addr());
+                    self.node = Some(node);
+                    self.reconnecting = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                // the thread died without a connection
+                Err(TryRecvError::Disconnected) => self.reconnecting = Some(Reconnect::start(self.opt.clone())),
+            }
+        }
+        self.node.as_mut()
+    }
+
+    /// Stop sending calls to the node and reconnect in the background.
+    fn mark_down(&mut self) {
+        self.node = None;
+        if self.reconnecting.is_none() {
+            self.reconnecting = Some(Reconnect::start(self.opt.clone()));
+        }
+    }
+
+    fn health(&self) -> NodeHealth {
+        match (&self.node, &self.reconnecting) {
+            (Some(node), _) if !node.is_broken() => NodeHealth::Healthy,
+            (None, Some(_)) => NodeHealth::Reconnecting,
+            _ => NodeHealth::Unhealthy,
+        }
+    }
+}
+
+/// Where the partitions live , as reported by `@Statistics TOPO`.
+struct Topology {
+    hashinator: Hashinator,
+    // partition id to host id of its leader
+    leaders: HashMap<i32, i32>,
+}
+
+/// How `Client` spreads calls which are not routed to a particular host , set with `Opts::with_load_balance`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum LoadBalanceStrategy {
+    /// Each call to the next host in turn.
+    #[default]
+    RoundRobin,
+    /// To the host with the fewest calls waiting for a response , taking turns among equals.
+    /// A slow host has more calls pending , so it gets fewer new ones.
+    LeastOutstanding,
+}
+
+/// Client connected to every host in `Opts`, spreading the calls as `Opts::with_load_balance` says , round-robin by default.
+/// A host failing a call is skipped and reconnected in the background , see `health`.
+/// Every host is subscribed to topology updates , which keep `call_sp_partitioned` routing right as partitions move.
+/// With `Opts::with_admin_port` it also connects to the admin port of the first host , for the admin procedures.
+pub struct Client {
+    nodes: Vec<ClientNode>,
+    admin: Option<ClientNode>,
+    next: usize,
+    load_balance: LoadBalanceStrategy,
+    topology: Option<Topology>,
+    // pushed by the servers , applied with the next call
+    topology_updates: Receiver<VoltTable>,
+    leader_addr: Option<Ipv4Addr>,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let healthy = self.nodes.iter().filter(|n| n.node.is_some()).count();
+        write!(f, "Client hosts: {}, connected: {}", self.nodes.len(), healthy)
+    }
+}
+
+impl Client {
+    /// Connect to all hosts, fails only if none of them can be reached.
+    pub fn new(opts: Opts) -> Result<Client, VoltError> {
+        let mut nodes = Vec::with_capacity(opts.0.ip_ports.len());
+        let mut last_err = VoltError::InvalidConfig;
+        let (tx, topology_updates) = mpsc::channel();
+        let on_topology_change: TopologyCallback = Arc::new(move |table| {
+            // the client may be gone
+            let _ = tx.send(table);
+        });
+        for i in 0..opts.0.ip_ports.len() {
+            let opt = NodeOpt {
+                on_topology_change: Some(Arc::clone(&on_topology_change)),
+                ..opts.to_node_opt(i).ok_or(VoltError::InvalidConfig)?
+            };
+            let (entry, err) = ClientNode::new(opt);
+            if let Some(err) = err {
+                last_err = err;
+            }
+            nodes.push(entry);
+        }
+        if nodes.iter().all(|n| n.node.is_none()) {
+            return Err(last_err);
+        }
+        let leader_addr = nodes.iter()
+            .find_map(|n| n.node.as_ref())
+            .map(|node| node.conn_info().leader_addr());
+        // the admin connection is only needed for admin procedures , so failing to open it is not fatal here
+        let admin = opts.to_admin_node_opt(0).map(|opt| ClientNode::new(opt).0);
+        let load_balance = opts.0.load_balance;
+        let mut client = Client { nodes, admin, next: 0, load_balance, topology: None, topology_updates, leader_addr };
+        // without topology calls are simply not routed by partition
+        let _ = client.refresh_topology();
+        Ok(client)
+    }
+
+    /// State of every host , in the order of `Opts`.
+    pub fn health(&self) -> Vec<NodeHealth> {
+        self.nodes.iter().map(|n| n.health()).collect()
+    }
+
+    /// Fetch the hashinator and partition leaders used by `call_sp_partitioned` again.
+    pub fn refresh_topology(&mut self) -> Result<(), VoltError> {
+        let mut table = block_for_result(&self.call_sp("@Statistics", volt_param!("TOPO", 0))?)?;
+        self.apply_topology(&mut table)
+    }
+
+    /// Take over the hashinator and partition leaders of a `@Statistics TOPO` result or topology update.
+    fn apply_topology(&mut self, table: &mut VoltTable) -> Result<(), VoltError> {
+        let leaders = partition_leaders(table)?;
+        let hash_table = table.extra_table(0).ok_or_else(|| VoltError::NoValue("HASHCONFIG".to_owned()))?;
+        let hashinator = Hashinator::from_topology(hash_table)?;
+        self.topology = Some(Topology { hashinator, leaders });
+        Ok(())
+    }
+
+    /// Apply the topology updates pushed since the last call , the last one wins.
+    fn apply_topology_updates(&mut self) {
+        while let Ok(mut table) = self.topology_updates.try_recv() {
+            if let Err(err) = self.apply_topology(&mut table) {
+                log_warn!("ignoring a topology update: {}", err);
+            }
+        }
+    }
+
+    /// Index of the node leading the partition `param` hashes to.
+    fn partition_owner(&self, param: &dyn Value) -> Option<usize> {
+        let topology = self.topology.as_ref()?;
+        let partition = topology.hashinator.partition_for_param(param).ok()?;
+        let host_id = *topology.leaders.get(&partition)?;
+        self.nodes.iter().position(|n| match &n.node {
+            Some(node) => node.conn_info().host_id() == host_id,
+            None => false,
+        })
+    }
+
+    /// Send a single partition procedure straight to the node leading the partition of
+    /// `param[partition_col_index]`, falling back to `call_sp` while the topology is unknown.
+    pub fn call_sp_partitioned(&mut self, query: &str, param: Vec<&dyn Value>, partition_col_index: usize) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.apply_topology_updates();
+        let owner = param.get(partition_col_index).and_then(|p| self.partition_owner(*p));
+        if let Some(rx) = owner.and_then(|idx| self.call_on(idx, query, &param)) {
+            return Ok(rx);
+        }
+        self.call_sp(query, param)
+    }
+
+    /// Call on one specific node if it is connected , marking it down when the call fails.
+    fn call_on(&mut self, idx: usize, query: &str, param: &[&dyn Value]) -> Option<Receiver<Result<VoltTable, VoltError>>> {
+        let entry = &mut self.nodes[idx];
+        let res = entry.node.as_mut()?.call_sp(query, param.to_vec());
+        if res.is_err() {
+            entry.mark_down();
+        }
+        res.ok()
+    }
+
+    /// Call over the admin connection , reconnecting it first if it is down.
+    fn call_admin(&mut self, query: &str, param: &[&dyn Value]) -> Option<Receiver<Result<VoltTable, VoltError>>> {
+        let admin = self.admin.as_mut()?;
+        let res = admin.connected(&mut self.leader_addr)?.call_sp(query, param.to_vec());
+        if res.is_err() {
+            admin.mark_down();
+        }
+        res.ok()
+    }
+
+    /// Index of the node connected to the cluster leader.
+    fn leader(&self) -> Option<usize> {
+        let leader = IpAddr::V4(self.leader_addr?);
+        self.nodes.iter().position(|n| n.node.as_ref().and_then(|n| n.peer_ip()) == Some(leader))
+    }
+
+    /// Spread over the connected nodes by the `LoadBalanceStrategy` , admin procedures like `@UpdateClasses` go to the leader first.
+    /// `@Pause` , `@Resume` and the like go over the admin connection while it is up.
+    pub fn call_sp(&mut self, query: &str, param: Vec<&dyn Value>) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.send(query, param).map(|(_, rx)| rx)
+    }
+
+    /// Like `call_sp`, but also returns the `ConnInfo` of the node the call was sent to ,
+    /// e.g. to see which host and server version handled it.
+    pub fn call_sp_with_info(&mut self, query: &str, param: Vec<&dyn Value>) -> Result<(ConnInfo, TableReceiver), VoltError> {
+        let (idx, rx) = self.send(query, param)?;
+        let entry = match idx {
+            Some(idx) => &self.nodes[idx],
+            None => self.admin.as_ref().ok_or(VoltError::ConnectionNotAvailable)?,
+        };
+        let info = entry.node.as_ref().ok_or(VoltError::ConnectionNotAvailable)?.conn_info();
+        Ok((info, rx))
+    }
+
+    /// Connection details of every host in the order of `Opts` , `None` while one is down.
+    pub fn nodes(&self) -> Vec<Option<ConnInfo>> {
+        self.nodes.iter().map(|n| n.node.as_ref().map(|node| node.conn_info())).collect()
+    }
+
+    /// Send the call as `call_sp` describes , with the index of the node it went to or `None` for the admin connection.
+    fn send(&mut self, query: &str, param: Vec<&dyn Value>) -> Result<(Option<usize>, TableReceiver), VoltError> {
+        self.apply_topology_updates();
+        if ADMIN_PROCEDURES.contains(&query) {
+            if let Some(rx) = self.call_admin(query, &param) {
+                return Ok((None, rx));
+            }
+        }
+        if LEADER_PROCEDURES.contains(&query) {
+            if let Some(leader) = self.leader() {
+                if let Some(rx) = self.call_on(leader, query, &param) {
+                    return Ok((Some(leader), rx));
+                }
+            }
+        }
+        // so a broken node starts reconnecting and a reconnected one is back even when it is not its turn
+        for entry in self.nodes.iter_mut() {
+            entry.connected(&mut self.leader_addr);
+        }
+        let total = self.nodes.len();
+        let mut order: Vec<usize> = (0..total).map(|i| self.next.wrapping_add(i) % total).collect();
+        if self.load_balance == LoadBalanceStrategy::LeastOutstanding {
+            // stable , so equals keep the round-robin order , nodes which are down come last
+            order.sort_by_key(|idx| self.nodes[*idx].node.as_ref().map_or(usize::MAX, |node| node.pending_requests()));
+        }
+        let mut last_err = VoltError::ConnectionNotAvailable;
+        for idx in order {
+            self.next = self.next.wrapping_add(1);
+            let entry = &mut self.nodes[idx];
+            let res = match entry.connected(&mut self.leader_addr) {
+                None => continue,
+                Some(node) => node.call_sp(query, param.clone()),
+            };
+            match res {
+                Ok(rx) => return Ok((Some(idx), rx)),
+                Err(err) => {
+                    entry.mark_down();
+                    last_err = err;
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Call and wait for the result , sending the call again as `policy` says when it fails for a transient reason.
+    /// Every attempt goes to the next node , so a retry gets around a host that went down.
+    pub fn call_sp_retry(&mut self, query: &str, param: Vec<&dyn Value>, policy: &RetryPolicy) -> Result<VoltTable, VoltError> {
+        policy.run(query, || block_for_result(&self.call_sp(query, param.clone())?))
+    }
+
+    pub fn list_procedures(&mut self) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.call_sp("@SystemCatalog", volt_param!("PROCEDURES"))
+    }
+
+    pub fn upload_jar(&mut self, bs: Vec<u8>) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.call_sp("@UpdateClasses", volt_param!(bs,""))
+    }
+
+    /// Like `Node::deploy_classes` , on the leader if it is connected.
+    pub fn deploy_classes(&mut self, jar: Vec<u8>, delete_pattern: &str) -> Result<VoltTable, VoltError> {
+        block_for_result(&self.call_sp("@UpdateClasses", volt_param!(jar, delete_pattern))?)
+    }
+
+    /// Use `@AdHoc` proc to query .
+    pub fn query(&mut self, sql: &str) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.query_with_params(sql, Vec::new())
+    }
+
+    /// Use `@AdHoc` proc to query , binding `params` to the `?` placeholders of `sql` in order.
+    pub fn query_with_params(&mut self, sql: &str, params: Vec<&dyn Value>) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        let mut all: Vec<&dyn Value> = Vec::with_capacity(params.len() + 1);
+        all.push(&sql);
+        all.extend(params);
+        self.call_sp("@AdHoc", all)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::mock_server::{answer_all, int_response, read_call, serve, tables_response, topology_tables, try_read_call};
+    use crate::PortRole;
+
+    use super::*;
+
+    /// Next call after the `@Subscribe` every connection of a client starts with.
+    fn read_client_call(stream: &mut std::net::TcpStream) -> (String, i64) {
+        let (name, handle) = read_call(stream);
+        assert_eq!((name.as_str(), handle), ("@Subscribe", crate::node::PING_HANDLE));
+        stream.write_all(&int_response(handle, 0)).unwrap();
+        read_call(stream)
+    }
+
+    #[test]
+    fn test_skip_broken_node() {
+        // answers the topology request , then goes away
+        let first = serve(|mut stream| {
+            let (_, handle) = read_client_call(&mut stream);
+            stream.write_all(&int_response(handle, 1)).unwrap();
+        });
+        let second = serve(|mut stream| answer_all(&mut stream, 2));
+        let mut client = Client::new(Opts::new(vec![first, second])).unwrap();
+        for _ in 0..100 {
+            if client.nodes[0].node.as_ref().is_some_and(|node| node.is_broken()) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        for _ in 0..4 {
+            let mut table = block_for_result(&client.query("select a from t").unwrap()).unwrap();
+            assert!(table.advance_row());
+            assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(2));
+        }
+        assert!(client.nodes[0].node.is_none());
+        let nodes = client.nodes();
+        assert!(nodes[0].is_none());
+        assert_eq!(nodes[1].as_ref().map(|info| info.host_id()), Some(1));
+        let (info, _) = client.call_sp_with_info("@AdHoc", volt_param!("select a from t")).unwrap();
+        assert_eq!(info.connection_id(), 2);
+    }
+
+    #[test]
+    fn test_admin_connection() {
+        let client_port = serve(|mut stream| answer_all(&mut stream, 1));
+        let admin_port = serve(|mut stream| answer_all(&mut stream, 2));
+        let opts = Opts::new(vec![client_port]).with_admin_port(admin_port.port);
+        let mut client = Client::new(opts).unwrap();
+        assert_eq!(client.admin.as_ref().and_then(|a| a.node.as_ref()).map(|n| n.role()), Some(PortRole::Admin));
+        let (info, rx) = client.call_sp_with_info("@Pause", volt_param!()).unwrap();
+        assert_eq!(info.build_string(), "test");
+        let mut table = block_for_result(&rx).unwrap();
+        assert!(table.advance_row());
+        assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(2));
+        let mut table = block_for_result(&client.query("select a from t").unwrap()).unwrap();
+        assert!(table.advance_row());
+        assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_least_outstanding() {
+        // answers the topology request , then nothing
+        let slow = serve(|mut stream| {
+            let (_, handle) = read_client_call(&mut stream);
+            stream.write_all(&int_response(handle, 1)).unwrap();
+            while try_read_call(&mut stream).is_some() {}
+        });
+        let fast = serve(|mut stream| answer_all(&mut stream, 2));
+        let opts = Opts::new(vec![slow, fast]).with_load_balance(LoadBalanceStrategy::LeastOutstanding);
+        let mut client = Client::new(opts).unwrap();
+        assert!(block_for_result(&client.query("select a from t").unwrap()).is_ok());
+        // nothing pending anywhere , so it is the turn of the slow one
+        let _pending = client.query("select a from t").unwrap();
+        for _ in 0..4 {
+            let mut table = block_for_result(&client.query("select a from t").unwrap()).unwrap();
+            assert!(table.advance_row());
+            assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(2));
+        }
+    }
+
+    #[test]
+    fn test_reconnect_in_background() {
+        // the first connection answers the topology request and goes away , later ones answer with 1
+        let connections = AtomicUsize::new(0);
+        let first = serve(move |mut stream| {
+            if connections.fetch_add(1, Ordering::Relaxed) == 0 {
+                let (_, handle) = read_client_call(&mut stream);
+                stream.write_all(&int_response(handle, 1)).unwrap();
+                return;
+            }
+            thread::spawn(move || answer_all(&mut stream, 1));
+        });
+        let second = serve(|mut stream| answer_all(&mut stream, 2));
+        let mut client = Client::new(Opts::new(vec![first, second])).unwrap();
+        client.nodes[0].opt.reconnect_interval = Duration::from_millis(20);
+        while client.health()[0] == NodeHealth::Healthy {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(client.health(), vec![NodeHealth::Unhealthy, NodeHealth::Healthy]);
+        assert!(block_for_result(&client.query("select a from t").unwrap()).is_ok());
+        assert_eq!(client.health(), vec![NodeHealth::Reconnecting, NodeHealth::Healthy]);
+        // back in the rotation once the new connection is up
+        let mut answers = vec![];
+        for _ in 0..100 {
+            let mut table = block_for_result(&client.query("select a from t").unwrap()).unwrap();
+            assert!(table.advance_row());
+            answers.push(table.get_i32_by_idx(0).unwrap());
+            if answers.contains(&Some(1)) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(answers.contains(&Some(1)));
+        assert_eq!(client.health(), vec![NodeHealth::Healthy, NodeHealth::Healthy]);
+    }
+
+    #[test]
+    fn test_topology_update() {
+        let ip_port = serve(|mut stream| {
+            // no topology to start with , it is pushed after the first query
+            let (name, handle) = read_client_call(&mut stream);
+            assert_eq!(name, "@Statistics");
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            let (_, handle) = read_call(&mut stream);
+            stream.write_all(&int_response(handle, 1)).unwrap();
+            stream.write_all(&tables_response(crate::node::TOPOLOGY_HANDLE, &topology_tables())).unwrap();
+            answer_all(&mut stream, 2);
+        });
+        let mut client = Client::new(Opts::new(vec![ip_port])).unwrap();
+        assert!(client.topology.is_none());
+        assert!(block_for_result(&client.query("select a from t").unwrap()).is_ok());
+        for _ in 0..100 {
+            if client.topology.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+            block_for_result(&client.query("select a from t").unwrap()).unwrap();
+        }
+        assert_eq!(client.topology.as_ref().map(|t| t.leaders.clone()), Some(HashMap::from([(0, 1)])));
+        let order = 7;
+        assert!(client.call_sp_partitioned("Insert", volt_param!(order), 0).is_ok());
+    }
+}
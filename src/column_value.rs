@@ -0,0 +1,53 @@
+use bigdecimal::BigDecimal;
+
+use crate::encode::{*};
+use crate::geography::GeographyPoint;
+use crate::table::Column;
+
+/// Value of a column of any type , for code that does not know the schema ahead of time.
+/// NULL of every type is `Null`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValue {
+    Null,
+    TinyInt(i8),
+    SmallInt(i16),
+    Integer(i32),
+    BigInt(i64),
+    Float(f64),
+    String(String),
+    /// Microseconds since the epoch , as the server sends it.
+    Timestamp(i64),
+    Decimal(BigDecimal),
+    VarBinary(Vec<u8>),
+    GeographyPoint(GeographyPoint),
+    /// The polygon in the binary format of the server.
+    Geography(Vec<u8>),
+}
+
+impl ColumnValue {
+    /// Decode the bytes `bs` of `column` , `VoltError::InvalidColumnType` for a type tables do not have.
+    pub(crate) fn decode(column: &Column, bs: &[u8]) -> Result<ColumnValue, VoltError> {
+        let bs = bs.to_vec();
+        let value = match column.header_type {
+            TINYINT_COLUMN => Option::<i8>::from_bytes(bs, column)?.map(ColumnValue::TinyInt),
+            SHORT_COLUMN => Option::<i16>::from_bytes(bs, column)?.map(ColumnValue::SmallInt),
+            INT_COLUMN => Option::<i32>::from_bytes(bs, column)?.map(ColumnValue::Integer),
+            LONG_COLUMN => Option::<i64>::from_bytes(bs, column)?.map(ColumnValue::BigInt),
+            FLOAT_COLUMN => Option::<f64>::from_bytes(bs, column)?.map(ColumnValue::Float),
+            STRING_COLUMN => Option::<String>::from_bytes(bs, column)?.map(ColumnValue::String),
+            // NULL is the same as for BIGINT
+            TIMESTAMP_COLUMN => Option::<i64>::from_bytes(bs, column)?.map(ColumnValue::Timestamp),
+            DECIMAL_COLUMN => Option::<BigDecimal>::from_bytes(bs, column)?.map(ColumnValue::Decimal),
+            VAR_BIN_COLUMN => Option::<Vec<u8>>::from_bytes(bs, column)?.map(ColumnValue::VarBinary),
+            GEOGRAPHY_POINT_COLUMN => Option::<GeographyPoint>::from_bytes(bs, column)?.map(ColumnValue::GeographyPoint),
+            // length prefixed like VARBINARY
+            GEOGRAPHY_COLUMN => Option::<Vec<u8>>::from_bytes(bs, column)?.map(ColumnValue::Geography),
+            tp => return Err(VoltError::InvalidColumnType(tp)),
+        };
+        Ok(value.unwrap_or(ColumnValue::Null))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, ColumnValue::Null)
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt::Display;
+
+use bigdecimal::BigDecimal;
+use bytebuffer::ByteBuffer;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::encode::{*};
+use crate::row::Row;
+use crate::table::{Column, VoltTable};
+
+impl de::Error for VoltError {
+    fn custom<T: Display>(msg: T) -> Self {
+        VoltError::Deserialize(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        VoltError::MissingField(field.to_owned())
+    }
+}
+
+impl VoltTable {
+    /// Deserialize every row into a `T` , struct fields are matched to the columns by name ignoring case.
+    /// Integer , FLOAT , VARCHAR and VARBINARY columns map to the matching Rust types ,
+    /// DECIMAL to its string , TIMESTAMP to microseconds since the epoch and GEOGRAPHY_POINT to a `(longitude, latitude)` pair.
+    /// NULL columns and columns missing from the table deserialize into `Option` fields as `None`.
+    pub fn deserialize_rows<T: DeserializeOwned>(&mut self) -> Result<Vec<T>, VoltError> {
+        self.advance_to_row(-1);
+        self.rows().map(|row| T::deserialize(RowDeserializer { row: &row? })).collect()
+    }
+}
+
+struct RowDeserializer<'a, 'b> {
+    row: &'b Row<'a>,
+}
+
+impl<'de, 'a, 'b> de::Deserializer<'de> for RowDeserializer<'a, 'b> {
+    type Error = VoltError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VoltError> {
+        let names = self.row.columns().iter().map(|c| c.header_name.as_str()).collect();
+        visitor.visit_map(RowAccess { row: self.row, names, next: None })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, VoltError> {
+        // absent fields are left to serde , which fails with `missing_field` unless they are optional
+        let names = fields.iter().copied().filter(|f| self.row.column_index(f).is_some()).collect();
+        visitor.visit_map(RowAccess { row: self.row, names, next: None })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VoltError> {
+        visitor.visit_seq(ColumnsAccess { row: self.row, idx: 0 })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, VoltError> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+struct RowAccess<'a, 'b> {
+    row: &'b Row<'a>,
+    names: Vec<&'b str>,
+    next: Option<&'b str>,
+}
+
+impl<'de, 'a, 'b> MapAccess<'de> for RowAccess<'a, 'b> {
+    type Error = VoltError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, VoltError> {
+        if self.names.is_empty() {
+            return Ok(None);
+        }
+        let name = self.names.remove(0);
+        self.next = Some(name);
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, VoltError> {
+        let name = self.next.take().ok_or_else(|| VoltError::Deserialize("value without a key".to_owned()))?;
+        let idx = self.row.column_index(name).ok_or_else(|| VoltError::NoSuchColumn(name.to_owned()))?;
+        seed.deserialize(ColumnDeserializer::new(self.row, idx)?)
+            .map_err(|e| match e {
+                VoltError::Deserialize(msg) => VoltError::Deserialize(format!("column {}: {}", name, msg)),
+                e => e,
+            })
+    }
+}
+
+struct ColumnsAccess<'a, 'b> {
+    row: &'b Row<'a>,
+    idx: i16,
+}
+
+impl<'de, 'a, 'b> SeqAccess<'de> for ColumnsAccess<'a, 'b> {
+    type Error = VoltError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, VoltError> {
+        if self.idx as usize >= self.row.columns().len() {
+            return Ok(None);
+        }
+        let value = seed.deserialize(ColumnDeserializer::new(self.row, self.idx)?)?;
+        self.idx += 1;
+        Ok(Some(value))
+    }
+}
+
+struct ColumnDeserializer<'b> {
+    column: &'b Column,
+    bytes: &'b [u8],
+}
+
+impl<'b> ColumnDeserializer<'b> {
+    fn new(row: &'b Row, idx: i16) -> Result<Self, VoltError> {
+        let column = row.columns().get(idx as usize).ok_or_else(|| VoltError::NoValue(idx.to_string()))?;
+        Ok(ColumnDeserializer { column, bytes: row.get_bytes_by_idx(idx)? })
+    }
+
+    fn is_null(&self) -> bool {
+        let null: &[u8] = match self.column.header_type {
+            TINYINT_COLUMN => &NULL_BIT_VALUE,
+            SHORT_COLUMN => &NULL_SHORT_VALUE,
+            INT_COLUMN => &NULL_INT_VALUE,
+            LONG_COLUMN => &NULL_LONG_VALUE,
+            FLOAT_COLUMN => &NULL_FLOAT_VALUE,
+            TIMESTAMP_COLUMN => &NULL_TIMESTAMP,
+            DECIMAL_COLUMN => &NULL_DECIMAL,
+            STRING_COLUMN | VAR_BIN_COLUMN => &NULL_VARCHAR,
+            GEOGRAPHY_POINT_COLUMN => return crate::geography::is_null_point(self.bytes),
+            _ => return self.column.header_type == NULL_COLUMN,
+        };
+        self.bytes == null
+    }
+
+    fn reader(&self) -> ByteBuffer {
+        ByteBuffer::from_bytes(self.bytes)
+    }
+}
+
+impl<'de, 'b> de::Deserializer<'de> for ColumnDeserializer<'b> {
+    type Error = VoltError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VoltError> {
+        if self.is_null() {
+            return visitor.visit_none();
+        }
+        let mut r = self.reader();
+        match self.column.header_type {
+            TINYINT_COLUMN => visitor.visit_i8(r.read_i8()?),
+            SHORT_COLUMN => visitor.visit_i16(r.read_i16()?),
+            INT_COLUMN => visitor.visit_i32(r.read_i32()?),
+            LONG_COLUMN | TIMESTAMP_COLUMN => visitor.visit_i64(r.read_i64()?),
+            FLOAT_COLUMN => visitor.visit_f64(r.read_f64()?),
+            STRING_COLUMN => visitor.visit_string(r.read_string()?),
+            VAR_BIN_COLUMN => visitor.visit_byte_buf(self.bytes[4..].to_vec()),
+            DECIMAL_COLUMN => visitor.visit_string(BigDecimal::from_bytes(self.bytes.to_vec(), self.column)?.to_string()),
+            GEOGRAPHY_POINT_COLUMN => {
+                let point = vec![r.read_f64()?, r.read_f64()?];
+                visitor.visit_seq(point.into_deserializer())
+            }
+            tp => Err(VoltError::InvalidColumnType(tp)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VoltError> {
+        if self.column.header_type == TINYINT_COLUMN && !self.is_null() {
+            return visitor.visit_bool(self.reader().read_i8()? != 0);
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, VoltError> {
+        if self.is_null() {
+            return visitor.visit_none();
+        }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, VoltError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::volt_param;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Account {
+        id: i64,
+        name: Option<String>,
+        active: bool,
+        balance: String,
+        note: Option<String>,
+    }
+
+    fn table() -> VoltTable {
+        let header = vec!["ID", "NAME", "ACTIVE", "BALANCE"].into_iter().map(|s| s.to_owned()).collect();
+        let mut table = VoltTable::new_table(vec![LONG_COLUMN, STRING_COLUMN, TINYINT_COLUMN, DECIMAL_COLUMN], header);
+        let (balance, none) = (BigDecimal::from(5), None::<String>);
+        table.add_row(volt_param! {1 as i64, "a", true, balance}).unwrap();
+        table.add_row(volt_param! {2 as i64, none, false, balance}).unwrap();
+        table
+    }
+
+    #[test]
+    fn test_deserialize_rows() {
+        let mut table = table();
+        let accounts: Vec<Account> = table.deserialize_rows().unwrap();
+        assert_eq!(accounts, vec![
+            Account { id: 1, name: Some("a".to_owned()), active: true, balance: "5.000000000000".to_owned(), note: None },
+            Account { id: 2, name: None, active: false, balance: "5.000000000000".to_owned(), note: None },
+        ]);
+        let tuples: Vec<(i64, Option<String>, i8, String)> = table.deserialize_rows().unwrap();
+        assert_eq!(tuples[0].0, 1);
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Missing {
+            id: i64,
+            email: String,
+        }
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Mismatch {
+            name: i64,
+        }
+        let mut table = table();
+        assert!(matches!(table.deserialize_rows::<Missing>(), Err(VoltError::MissingField(f)) if f == "email"));
+        match table.deserialize_rows::<Mismatch>() {
+            Err(VoltError::Deserialize(msg)) => assert!(msg.starts_with("column name:"), "{}", msg),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -1,18 +1,18 @@
 use std::fmt::Debug;
+use std::num::TryFromIntError;
 use std::str::Utf8Error;
 use std::sync::PoisonError;
 
 use bigdecimal::BigDecimal;
 use bigdecimal::num_bigint::BigInt;
 use bytebuffer::ByteBuffer;
-use chrono::{DateTime, Utc};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeZone, Utc};
 use quick_error::quick_error;
 
-use crate::chrono::TimeZone;
 use crate::Column;
 use crate::response::VoltResponseInfo;
 
-#[allow(dead_code)]
 pub const ARRAY_COLUMN: i8 = -99;
 pub const NULL_COLUMN: i8 = 1;
 pub const TINYINT_COLUMN: i8 = 3;
@@ -25,6 +25,82 @@ pub const TIMESTAMP_COLUMN: i8 = 11;
 pub const TABLE: i8 = 21;
 pub const DECIMAL_COLUMN: i8 = 22;
 pub const VAR_BIN_COLUMN: i8 = 25; // varbinary (int)(bytes)
+pub const GEOGRAPHY_POINT_COLUMN: i8 = 26; // longitude and latitude (f64)(f64)
+pub const GEOGRAPHY_COLUMN: i8 = 27; // polygon (int)(bytes)
+
+/// Type of a value on the wire , `Unknown` keeps the type byte of one this client does not know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Null,
+    TinyInt,
+    SmallInt,
+    Integer,
+    BigInt,
+    Float,
+    String,
+    Timestamp,
+    Table,
+    Decimal,
+    VarBinary,
+    GeographyPoint,
+    Geography,
+    Array,
+    Unknown(i8),
+}
+
+impl ColumnType {
+    pub fn from_wire(tp: i8) -> Self {
+        match tp {
+            NULL_COLUMN => ColumnType::Null,
+            TINYINT_COLUMN => ColumnType::TinyInt,
+            SHORT_COLUMN => ColumnType::SmallInt,
+            INT_COLUMN => ColumnType::Integer,
+            LONG_COLUMN => ColumnType::BigInt,
+            FLOAT_COLUMN => ColumnType::Float,
+            STRING_COLUMN => ColumnType::String,
+            TIMESTAMP_COLUMN => ColumnType::Timestamp,
+            TABLE => ColumnType::Table,
+            DECIMAL_COLUMN => ColumnType::Decimal,
+            VAR_BIN_COLUMN => ColumnType::VarBinary,
+            GEOGRAPHY_POINT_COLUMN => ColumnType::GeographyPoint,
+            GEOGRAPHY_COLUMN => ColumnType::Geography,
+            ARRAY_COLUMN => ColumnType::Array,
+            tp => ColumnType::Unknown(tp),
+        }
+    }
+
+    pub fn to_wire(self) -> i8 {
+        match self {
+            ColumnType::Null => NULL_COLUMN,
+            ColumnType::TinyInt => TINYINT_COLUMN,
+            ColumnType::SmallInt => SHORT_COLUMN,
+            ColumnType::Integer => INT_COLUMN,
+            ColumnType::BigInt => LONG_COLUMN,
+            ColumnType::Float => FLOAT_COLUMN,
+            ColumnType::String => STRING_COLUMN,
+            ColumnType::Timestamp => TIMESTAMP_COLUMN,
+            ColumnType::Table => TABLE,
+            ColumnType::Decimal => DECIMAL_COLUMN,
+            ColumnType::VarBinary => VAR_BIN_COLUMN,
+            ColumnType::GeographyPoint => GEOGRAPHY_POINT_COLUMN,
+            ColumnType::Geography => GEOGRAPHY_COLUMN,
+            ColumnType::Array => ARRAY_COLUMN,
+            ColumnType::Unknown(tp) => tp,
+        }
+    }
+}
+
+impl From<i8> for ColumnType {
+    fn from(tp: i8) -> Self {
+        ColumnType::from_wire(tp)
+    }
+}
+
+impl From<ColumnType> for i8 {
+    fn from(tp: ColumnType) -> Self {
+        tp.to_wire()
+    }
+}
 
 
 pub const NULL_DECIMAL: [u8; 16] = [128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
@@ -41,6 +117,9 @@ pub const NULL_VARCHAR: [u8; 4] = [255, 255, 255, 255];
 
 
 quick_error! {
+/// Everything a call can fail with.
+/// `?` converts `std::io::Error` , `std::str::Utf8Error` , `std::sync::mpsc::RecvError` , `std::sync::PoisonError`
+/// and `std::num::TryFromIntError` into it , so code extending the client can use the same error type.
 #[derive(Debug)]
 pub enum VoltError {
         Io(err: std::io::Error) {
@@ -56,20 +135,35 @@ pub enum VoltError {
         }
 
         ExecuteFail ( info: VoltResponseInfo ){
-            display("volt execute failed: {:?}", info)
+            display("Call {} failed with status {} ({:?}): {}", info.handle(), info.status(), info.get_status(), info.status_string())
         }
         InvalidColumnType(tp: i8) {
-            display("InvalidColumnType {}", tp)
+            display("Invalid column type {}", tp)
         }
 
         NoValue (descr : String) {
-            display("Error {}", descr)
+            display("No value for {}", descr)
+        }
+        UnexpectedNull (column: String) {
+            display("Column {} is NULL", column)
+        }
+        MissingField (field: String) {
+            display("No column for field {}", field)
+        }
+        Deserialize (descr: String) {
+            display("Deserialize error: {}", descr)
+        }
+        NoSuchColumn (column: String) {
+            display("No column named {}", column)
+        }
+        TypeMismatch (column: String, expected: i8, actual: i8) {
+            display("Column {} has type {}, expected {}", column, actual, expected)
         }
         Other(descr: String) {
-            display("Error {}", descr)
+            display("{}", descr)
         }
         NegativeNumTables (num: i16) {
-             display("Error {}", num)
+             display("Negative number of tables {}", num)
         }
 
          Utf8Error(err : Utf8Error){
@@ -78,22 +172,78 @@ pub enum VoltError {
             source(err)
         }
 
+        TryFromIntError(err: TryFromIntError) {
+            from()
+            display("Integer conversion error: {}", err)
+            source(err)
+        }
+
         PoisonError (descr: String){
-              display("Error {}", descr)
+              display("Lock poisoned: {}", descr)
         }
         BadReturnStatusOnTable (status: i8) {
-             display("Error {}", status)
+             display("Bad return status {} on table", status)
         }
         AuthFailed {
-             display("Auth failed")
+             display("Authentication failed, check user and password")
         }
         ConnectionNotAvailable {
-             display("Connection lost")
+             display("Connection not available")
+        }
+        ConnectTimeout {
+             display("Timed out connecting to the server")
+        }
+        ConnectFailed (errors: Vec<VoltError>) {
+             display("Could not connect to any address: {}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))
+        }
+        LoginTimeout {
+             display("Timed out logging in to the server")
+        }
+        ConnectionReset {
+             display("Connection reset, the request may or may not have been executed")
+        }
+        BackpressureExceeded {
+             display("Too many outstanding requests")
+        }
+        InvalidAddress (addr: String) {
+             display("Invalid address {}, expected host:port", addr)
+        }
+        Tls (descr: String) {
+             display("TLS error: {}", descr)
         }
        InvalidConfig {
-             display("Invalid Config")
+             display("Invalid config")
+        }
+        Timeout {
+             display("Timed out waiting for a response")
+        }
+        Cancelled {
+             display("Call cancelled before its response arrived")
+        }
+        UnsupportedServerVersion (build: String, min: String) {
+             display("Server {} is older than the required version {}", build, min)
+        }
+        DecodeError { handle: i64, bytes: String, err: Box<VoltError> } {
+             display("Could not decode the response to call {}: {} , response {}", handle, err, bytes)
+             source(&**err)
+        }
+        EmptyResponse {
+             display("Empty message from the server")
+        }
+        ResponseTooLarge (size: usize, max: usize) {
+             display("Response of {} bytes is larger than the maximum of {}", size, max)
+        }
+        ValueOutOfRange (descr: String) {
+             display("Value out of range: {}", descr)
+        }
+        StatementFailed { statement: usize, err: Box<VoltError> } {
+             display("Statement {} of the batch failed: {}", statement, err)
+             source(&**err)
+        }
+        RetriesExhausted (attempts: u32, last: Box<VoltError>) {
+             display("Gave up after {} attempts: {}", attempts, last)
+             source(&**last)
         }
-        Timeout
 
 
 }}
@@ -113,6 +263,17 @@ pub trait Value: Debug {
     fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8);
     fn to_value_string(&self) -> String;
     fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> where Self: Sized;
+    /// Fail when the value can not be sent , checked before a call or row is encoded.
+    fn check(&self) -> Result<(), VoltError> {
+        Ok(())
+    }
+}
+
+
+/// A struct whose fields are the parameters of a procedure , in declaration order.
+/// With the `derive` feature `#[derive(VoltParams)]` implements it.
+pub trait VoltParams {
+    fn volt_params(&self) -> Vec<&dyn Value>;
 }
 
 
@@ -173,7 +334,9 @@ impl Value for BigDecimal {
         let bs = b.to_signed_bytes_be();
         let pad = 16 - bs.len();
         if pad > 0 {
-            let arr = vec![0; pad];
+            // sign extend , a negative value is padded with ones
+            let fill = if b.sign() == bigdecimal::num_bigint::Sign::Minus { 0xff } else { 0 };
+            let arr = vec![fill; pad];
             bytebuffer.write_bytes(&arr)
         }
         bytebuffer.write_bytes(&bs);
@@ -320,14 +483,15 @@ impl Value for i32 {
     }
 }
 
+/// Sent as a BIGINT , as VoltDB has no unsigned types. In an INTEGER column of a table it takes 4 bytes.
 impl Value for u32 {
     fn get_write_length(&self) -> i32 {
-        return 5;
+        9
     }
 
     fn marshal(&self, bytebuffer: &mut ByteBuffer) {
-        bytebuffer.write_i8(INT_COLUMN);
-        bytebuffer.write_u32(*self);
+        bytebuffer.write_i8(LONG_COLUMN);
+        bytebuffer.write_i64(*self as i64);
     }
 
     fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
@@ -341,8 +505,8 @@ impl Value for u32 {
 
     fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
         let mut buffer = ByteBuffer::from_bytes(&bs);
-        let value = buffer.read_u32()?;
-        return Ok(value);
+        let value = buffer.read_i32()?;
+        u32::try_from(value).map_err(|_| VoltError::ValueOutOfRange(format!("{} does not fit a u32", value)))
     }
 }
 
@@ -372,6 +536,7 @@ impl Value for i64 {
     }
 }
 
+/// Sent as a BIGINT , values above `i64::MAX` fail with `VoltError::ValueOutOfRange` instead of turning negative.
 impl Value for u64 {
     fn get_write_length(&self) -> i32 {
         return 9;
@@ -393,8 +558,15 @@ impl Value for u64 {
 
     fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
         let mut buffer = ByteBuffer::from_bytes(&bs);
-        let value = buffer.read_u64()?;
-        return Ok(value);
+        let value = buffer.read_i64()?;
+        u64::try_from(value).map_err(|_| VoltError::ValueOutOfRange(format!("{} does not fit a u64", value)))
+    }
+
+    fn check(&self) -> Result<(), VoltError> {
+        if *self > i64::MAX as u64 {
+            return Err(VoltError::ValueOutOfRange(format!("{} does not fit a BIGINT", self)));
+        }
+        Ok(())
     }
 }
 
@@ -423,6 +595,38 @@ impl Value for f64 {
     }
 }
 
+/// FLOAT is a double on the wire , an `f32` is widened to one.
+impl Value for f32 {
+    fn get_write_length(&self) -> i32 {
+        9
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(FLOAT_COLUMN);
+        bytebuffer.write_f64(*self as f64);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+        bytebuffer.write_f64(*self as f64);
+    }
+
+    fn to_value_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
+        narrow_f64(f64::from_bytes(bs, _column)?)
+    }
+}
+
+/// `value` as an `f32` , failing with `VoltError::ValueOutOfRange` instead of becoming infinite.
+/// Precision is lost as with `as`.
+pub(crate) fn narrow_f64(value: f64) -> Result<f32, VoltError> {
+    if value.is_finite() && value.abs() > f32::MAX as f64 {
+        return Err(VoltError::ValueOutOfRange(format!("{} does not fit an f32", value)));
+    }
+    Ok(value as f32)
+}
 
 impl Value for String {
     fn get_write_length(&self) -> i32 {
@@ -511,24 +715,158 @@ impl Value for Vec<u8> {
     }
 
     fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
+        if bs == NULL_VARCHAR {
+            return Err(VoltError::UnexpectedNull(_column.header_name.clone()));
+        }
         let mut cp = bs.clone();
         cp.drain(0..4);
         return Ok(cp);
     }
 }
 
+impl Value for &[u8] {
+    fn get_write_length(&self) -> i32 {
+        (5 + self.len()) as i32
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(VAR_BIN_COLUMN);
+        self.marshal_in_table(bytebuffer, VAR_BIN_COLUMN);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+        bytebuffer.write_u32(self.len() as u32);
+        bytebuffer.write_bytes(self);
+    }
 
+    fn to_value_string(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn from_bytes(_bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
+        Err(VoltError::Other("read VARBINARY as a Vec<u8>".to_owned()))
+    }
+}
+
+
+/// Scale of every DECIMAL value , VoltDB sends them as a 128 bit integer of units of 10^-12.
+pub const DECIMAL_SCALE: u32 = 12;
+
+/// Largest number of digits of a DECIMAL , the unscaled value of every one is below 10^38 in magnitude.
+#[cfg(feature = "rust_decimal")]
+const DECIMAL_PRECISION: u32 = 38;
+
+/// The 128 bit integer VoltDB stores for `d`, rounding digits past the twelfth decimal.
+/// `None` when `d` has more than 26 digits before the point , which no DECIMAL holds.
+#[cfg(feature = "rust_decimal")]
+pub(crate) fn decimal_to_unscaled(d: &rust_decimal::Decimal) -> Option<i128> {
+    let mut d = *d;
+    if d.scale() > DECIMAL_SCALE {
+        d.rescale(DECIMAL_SCALE);
+    }
+    d.mantissa().checked_mul(10i128.pow(DECIMAL_SCALE - d.scale()))
+        .filter(|unscaled| unscaled.unsigned_abs() < 10u128.pow(DECIMAL_PRECISION))
+}
+
+#[cfg(feature = "rust_decimal")]
+impl Value for rust_decimal::Decimal {
+    fn get_write_length(&self) -> i32 {
+        17
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(DECIMAL_COLUMN);
+        self.marshal_in_table(bytebuffer, DECIMAL_COLUMN);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+        // `check` keeps values out of range from calls and rows , should one get here the server rejects
+        // `i128::MAX` rather than storing NULL , which `i128::MIN` would be
+        let unscaled = decimal_to_unscaled(self).unwrap_or(i128::MAX);
+        bytebuffer.write_bytes(&unscaled.to_be_bytes());
+    }
+
+    fn to_value_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> where Self: Sized {
+        let bs: [u8; 16] = bs.as_slice().try_into().map_err(|_| VoltError::NoValue("DECIMAL of 16 bytes".to_owned()))?;
+        let unscaled = i128::from_be_bytes(bs);
+        rust_decimal::Decimal::try_from_i128_with_scale(unscaled, DECIMAL_SCALE)
+            .map_err(|e| VoltError::Other(format!("DECIMAL {} does not fit rust_decimal: {}", unscaled, e)))
+    }
+
+    fn check(&self) -> Result<(), VoltError> {
+        match decimal_to_unscaled(self) {
+            Some(_) => Ok(()),
+            None => Err(VoltError::ValueOutOfRange(format!("{} does not fit a DECIMAL", self))),
+        }
+    }
+}
+
+/// SQL NULL for a parameter whose type is not known , `None` of an `Option` does the same for a typed one.
+/// The server converts it to the type of the column or parameter it is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Null;
+
+impl Value for Null {
+    fn get_write_length(&self) -> i32 {
+        1
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(NULL_COLUMN);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, column_type: i8) {
+        match column_type {
+            TINYINT_COLUMN => bytebuffer.write_bytes(&NULL_BIT_VALUE),
+            SHORT_COLUMN => bytebuffer.write_bytes(&NULL_SHORT_VALUE),
+            INT_COLUMN => bytebuffer.write_bytes(&NULL_INT_VALUE),
+            LONG_COLUMN => bytebuffer.write_bytes(&NULL_LONG_VALUE),
+            FLOAT_COLUMN => bytebuffer.write_bytes(&NULL_FLOAT_VALUE),
+            TIMESTAMP_COLUMN => bytebuffer.write_bytes(&NULL_TIMESTAMP),
+            DECIMAL_COLUMN => bytebuffer.write_bytes(&NULL_DECIMAL),
+            STRING_COLUMN | VAR_BIN_COLUMN => bytebuffer.write_bytes(&NULL_VARCHAR),
+            _ => {}
+        }
+    }
+
+    fn to_value_string(&self) -> String {
+        "NULL".to_owned()
+    }
+
+    fn from_bytes(_bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
+        Ok(Null)
+    }
+}
+
+/// Microseconds since the epoch , the TIMESTAMP encoding of VoltDB.
+#[cfg(feature = "chrono")]
+pub(crate) fn time_to_micros(time: &DateTime<Utc>) -> i64 {
+    time.timestamp() * 1_000_000 + time.timestamp_subsec_micros() as i64
+}
+
+#[cfg(feature = "chrono")]
+pub(crate) fn micros_to_time(micros: i64) -> Result<DateTime<Utc>, VoltError> {
+    let nanos = (micros.rem_euclid(1_000_000) * 1000) as u32;
+    Utc.timestamp_opt(micros.div_euclid(1_000_000), nanos).single()
+        .ok_or_else(|| VoltError::Other(format!("timestamp {} out of range", micros)))
+}
+
+#[cfg(feature = "chrono")]
 impl Value for DateTime<Utc> {
     fn get_write_length(&self) -> i32 {
         return 9;
     }
     fn marshal(&self, bytebuffer: &mut ByteBuffer) {
         bytebuffer.write_i8(TIMESTAMP_COLUMN);
-        bytebuffer.write_i64(self.timestamp_millis() * 1000);
+        bytebuffer.write_i64(time_to_micros(self));
     }
 
     fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
-        bytebuffer.write_i64(self.timestamp_millis() * 1000);
+        bytebuffer.write_i64(time_to_micros(self));
     }
 
     fn to_value_string(&self) -> String {
@@ -537,8 +875,7 @@ impl Value for DateTime<Utc> {
 
     fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> where Self: Sized {
         let mut buffer = ByteBuffer::from_bytes(&bs);
-        let time = buffer.read_i64()?;
-        return Ok(Utc.timestamp_millis(time / 1000));
+        micros_to_time(buffer.read_i64()?)
     }
 }
 
@@ -547,12 +884,22 @@ mod tests {
     use std::str::FromStr;
 
     use bigdecimal::num_bigint::BigInt;
-    use chrono::TimeZone;
 
     use crate::procedure_invocation::new_procedure_invocation;
 
     use super::*;
 
+    #[test]
+    fn test_column_type_wire() {
+        for tp in [ARRAY_COLUMN, NULL_COLUMN, TINYINT_COLUMN, SHORT_COLUMN, INT_COLUMN, LONG_COLUMN, FLOAT_COLUMN, STRING_COLUMN,
+            TIMESTAMP_COLUMN, TABLE, DECIMAL_COLUMN, VAR_BIN_COLUMN, GEOGRAPHY_POINT_COLUMN, GEOGRAPHY_COLUMN, 100] {
+            assert_eq!(ColumnType::from_wire(tp).to_wire(), tp);
+        }
+        assert_eq!(ColumnType::from_wire(GEOGRAPHY_COLUMN), ColumnType::Geography);
+        assert_eq!(ColumnType::from_wire(100), ColumnType::Unknown(100));
+        assert_eq!(i8::from(ColumnType::Array), ARRAY_COLUMN);
+    }
+
     #[test]
     fn test_encoding_proc() {
         let mut zero_vec: Vec<&dyn Value> = Vec::new();
@@ -563,10 +910,93 @@ mod tests {
         assert_eq!(bs, vec!(0, 0, 0, 56, 0, 0, 0, 0, 6, 64, 65, 100, 72, 111, 99, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 9, 0, 0, 0, 30, 115, 101, 108, 101, 99, 116, 32, 42, 32, 102, 114, 111, 109, 32, 97, 99, 99, 111, 117, 110, 116, 32, 108, 105, 109, 105, 116, 32, 49, 59));
     }
 
+    #[test]
+    fn test_no_read_only_flag() {
+        // the byte after the length is the invocation version , a read-only flag there would be taken as another format
+        let params: Vec<&dyn Value> = vec![&1i32];
+        let read_only = new_procedure_invocation(1, true, &params, "p").bytes();
+        assert_eq!(read_only, new_procedure_invocation(1, false, &params, "p").bytes());
+        assert_eq!(read_only[4], 0);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_volt_params() {
+        use crate::VoltParams;
+
+        #[derive(VoltParams)]
+        struct Insert {
+            id: i64,
+            name: &'static str,
+            note: Option<String>,
+        }
+        #[derive(VoltParams)]
+        struct Pair(i32, i32);
+
+        let insert = Insert { id: 1, name: "a", note: None };
+        let expected: Vec<&dyn Value> = vec![&insert.id, &insert.name, &insert.note];
+        let bytes = |params: &Vec<&dyn Value>| new_procedure_invocation(1, false, params, "p").bytes();
+        assert_eq!(bytes(&insert.volt_params()), bytes(&expected));
+        assert_eq!(Pair(3, 4).volt_params().len(), 2);
+    }
+
+    #[test]
+    fn test_encoding_null_params() {
+        let none_long: Option<i64> = None;
+        let none_float: Option<f64> = None;
+        let none_string: Option<String> = None;
+        let params: Vec<&dyn Value> = vec![&none_long, &none_float, &none_string, &Null];
+        let bs = new_procedure_invocation(1, false, &params, "p").bytes();
+        // length, version, name, handle, then the parameters
+        let mut expected: Vec<u8> = vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 112, 0, 0, 0, 0, 0, 0, 0, 1, 0, 4];
+        expected.push(LONG_COLUMN as u8);
+        expected.extend_from_slice(&NULL_LONG_VALUE);
+        expected.push(FLOAT_COLUMN as u8);
+        expected.extend_from_slice(&NULL_FLOAT_VALUE);
+        expected.push(STRING_COLUMN as u8);
+        expected.extend_from_slice(&NULL_VARCHAR);
+        expected.push(NULL_COLUMN as u8);
+        expected[3] = (expected.len() - 4) as u8;
+        assert_eq!(bs, expected);
+
+        let mut in_table = ByteBuffer::new();
+        Null.marshal_in_table(&mut in_table, INT_COLUMN);
+        Null.marshal_in_table(&mut in_table, STRING_COLUMN);
+        assert_eq!(in_table.into_vec(), vec![128, 0, 0, 0, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_unsigned_range() {
+        let column = Column { header_name: "".to_owned(), header_type: LONG_COLUMN };
+        let max = i64::MAX as u64;
+        assert!(max.check().is_ok());
+        assert!(matches!((max + 1).check(), Err(VoltError::ValueOutOfRange(_))));
+        assert!(matches!(Some(u64::MAX).check(), Err(VoltError::ValueOutOfRange(_))));
+        assert!(None::<u64>.check().is_ok());
+        let mut bf = ByteBuffer::new();
+        max.marshal(&mut bf);
+        u32::MAX.marshal(&mut bf);
+        assert_eq!(bf.into_vec(), [vec![LONG_COLUMN as u8, 127, 255, 255, 255, 255, 255, 255, 255], vec![LONG_COLUMN as u8, 0, 0, 0, 0, 255, 255, 255, 255]].concat());
+        assert_eq!(u64::from_bytes(max.to_be_bytes().to_vec(), &column).unwrap(), max);
+        assert!(matches!(u64::from_bytes((-1i64).to_be_bytes().to_vec(), &column), Err(VoltError::ValueOutOfRange(_))));
+        assert!(matches!(u32::from_bytes((-1i32).to_be_bytes().to_vec(), &column), Err(VoltError::ValueOutOfRange(_))));
+        let too_big = max + 1;
+        let params: Vec<&dyn Value> = vec![&1i64, &too_big];
+        assert!(matches!(crate::procedure_invocation::check_params(&params), Err(VoltError::ValueOutOfRange(_))));
+    }
+
+    #[cfg(feature = "chrono")]
     #[test]
     fn test_time_stamp() {
-        let time = Utc.timestamp_millis(1637323002445000 / 1000);
-        println!("{}", time.timestamp_millis() * 1000);
+        let column = Column { header_name: "T".to_owned(), header_type: TIMESTAMP_COLUMN };
+        for micros in [1637323002445123, 0, -1, -1637323002445123] {
+            let time = micros_to_time(micros).unwrap();
+            assert_eq!(time_to_micros(&time), micros);
+            let mut bs = ByteBuffer::new();
+            time.marshal_in_table(&mut bs, TIMESTAMP_COLUMN);
+            assert_eq!(DateTime::<Utc>::from_bytes(bs.into_vec(), &column).unwrap(), time);
+        }
+        assert_eq!(micros_to_time(-1).unwrap().timestamp_subsec_micros(), 999_999);
     }
 
     #[test]
@@ -582,6 +1012,82 @@ mod tests {
         println!("{:?}", decimal.into_bigint_and_exponent());
     }
 
+    #[test]
+    fn test_negative_big_decimal() {
+        let column = Column { header_name: "D".to_owned(), header_type: DECIMAL_COLUMN };
+        for s in ["-3", "-0.000000000001", "-12345678901234.5", "7", "0"] {
+            let decimal = BigDecimal::from_str(s).unwrap();
+            let mut bs = ByteBuffer::new();
+            decimal.marshal_in_table(&mut bs, DECIMAL_COLUMN);
+            let bs = bs.into_vec();
+            assert_eq!(bs.len(), 16);
+            assert_eq!(BigDecimal::from_bytes(bs, &column).unwrap(), decimal);
+        }
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_rust_decimal() {
+        use rust_decimal::Decimal;
+
+        let column = Column { header_name: "D".to_owned(), header_type: DECIMAL_COLUMN };
+        for s in ["-3", "-0.000000000001", "-12345678901234.5", "7.25", "0", "1.0000000000004"] {
+            let decimal = Decimal::from_str(s).unwrap();
+            let mut bs = ByteBuffer::new();
+            decimal.marshal_in_table(&mut bs, DECIMAL_COLUMN);
+            let bs = bs.into_vec();
+            // the same bytes as the BigDecimal encoding
+            let mut big = ByteBuffer::new();
+            BigDecimal::from_str(s).unwrap().with_scale(12).marshal_in_table(&mut big, DECIMAL_COLUMN);
+            assert_eq!(bs, big.into_vec(), "{}", s);
+            let back = Decimal::from_bytes(bs, &column).unwrap();
+            assert_eq!(back, decimal.round_dp(12));
+        }
+        assert_eq!(decimal_to_unscaled(&Decimal::from_str("-1.5").unwrap()), Some(-1_500_000_000_000));
+        // 26 digits before the point fit , 27 do not
+        let widest = Decimal::from_str("99999999999999999999999999.5").unwrap();
+        assert!(widest.check().is_ok());
+        assert!(matches!((widest * Decimal::TEN).check(), Err(VoltError::ValueOutOfRange(_))));
+        // overflowing 128 bits is refused too , instead of ending up as the NULL sentinel
+        for out_of_range in [Decimal::MIN, Decimal::MAX] {
+            assert!(matches!(out_of_range.check(), Err(VoltError::ValueOutOfRange(_))));
+            assert!(matches!(crate::procedure_invocation::check_params(&[&out_of_range]), Err(VoltError::ValueOutOfRange(_))));
+            let mut bs = ByteBuffer::new();
+            out_of_range.marshal_in_table(&mut bs, DECIMAL_COLUMN);
+            assert_ne!(bs.into_vec(), NULL_DECIMAL.to_vec());
+        }
+        let none: Option<Decimal> = Option::from_bytes(NULL_DECIMAL.to_vec(), &column).unwrap();
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn test_var_binary() {
+        let column = Column { header_name: "B".to_owned(), header_type: VAR_BIN_COLUMN };
+        let data: Vec<u8> = vec![1, 2, 3];
+        let empty: Vec<u8> = vec![];
+        let none: Option<Vec<u8>> = None;
+        let some: Option<Vec<u8>> = Some(data.clone());
+        let slice = data.as_slice();
+        let params: Vec<&dyn Value> = vec![&data, &slice, &empty, &none, &some];
+        let mut bs = ByteBuffer::new();
+        for p in &params {
+            p.marshal(&mut bs);
+        }
+        let total: i32 = params.iter().map(|p| p.get_write_length()).sum();
+        assert_eq!(bs.len(), total as usize);
+        let bs = bs.into_vec();
+        let with_data = [VAR_BIN_COLUMN as u8, 0, 0, 0, 3, 1, 2, 3];
+        assert_eq!(&bs[0..8], &with_data);
+        assert_eq!(&bs[8..16], &with_data);
+        assert_eq!(&bs[16..21], &[VAR_BIN_COLUMN as u8, 0, 0, 0, 0]);
+        assert_eq!(&bs[21..26], &[VAR_BIN_COLUMN as u8, 255, 255, 255, 255]);
+        assert_eq!(&bs[26..34], &with_data);
+
+        assert_eq!(Vec::<u8>::from_bytes(vec![0, 0, 0, 0], &column).unwrap(), empty);
+        assert!(matches!(Vec::<u8>::from_bytes(NULL_VARCHAR.to_vec(), &column), Err(VoltError::UnexpectedNull(_))));
+        assert_eq!(Option::<Vec<u8>>::from_bytes(vec![0, 0, 0, 0], &column).unwrap(), Some(empty));
+    }
+
     #[test]
     fn test_big_test_bytes() {
         let i = ByteBuffer::from_bytes(&NULL_BIT_VALUE).read_i8().unwrap();
@@ -644,15 +1150,62 @@ mod tests {
         assert_eq!(None, op);
 
 
-        let vec = NULL_TIMESTAMP.to_vec();
-        let op: Option<DateTime<Utc>> = Option::from_bytes(vec, &column).unwrap();
-        assert_eq!(None, op);
+        #[cfg(feature = "chrono")]
+        {
+            let vec = NULL_TIMESTAMP.to_vec();
+            let op: Option<DateTime<Utc>> = Option::from_bytes(vec, &column).unwrap();
+            assert_eq!(None, op);
+        }
     }
 
     #[test]
     fn test_error() {
         let err = VoltError::NoValue("key is af".to_owned());
         println!("{:?} {} ", err, err);
+        assert_eq!(err.to_string(), "No value for key is af");
+        assert_eq!(VoltError::Timeout.to_string(), "Timed out waiting for a response");
+        assert_eq!(VoltError::InvalidAddress("localhost".to_owned()).to_string(), "Invalid address localhost, expected host:port");
+
+        let err: VoltError = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "pipe").into();
+        assert_eq!(err.to_string(), "I/O error: pipe");
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "pipe");
+        let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(err);
+        assert!(boxed.source().is_some());
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        fn convert(case: u8) -> Result<(), VoltError> {
+            match case {
+                0 => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+                1 => {
+                    let invalid = vec![0xff];
+                    std::str::from_utf8(&invalid)?;
+                }
+                2 => {
+                    let (tx, rx) = std::sync::mpsc::channel::<()>();
+                    drop(tx);
+                    rx.recv()?;
+                }
+                3 => { i8::try_from(300)?; }
+                _ => {
+                    let lock = std::sync::Arc::new(std::sync::Mutex::new(()));
+                    let poisoned = std::sync::Arc::clone(&lock);
+                    let _ = std::thread::spawn(move || {
+                        let _guard = poisoned.lock().unwrap();
+                        panic!("poison");
+                    }).join();
+                    drop(lock.lock()?);
+                }
+            }
+            Ok(())
+        }
+        assert!(matches!(convert(0), Err(VoltError::Io(_))));
+        assert!(matches!(convert(1), Err(VoltError::Utf8Error(_))));
+        assert!(matches!(convert(2), Err(VoltError::RecvError(_))));
+        assert!(matches!(convert(3), Err(VoltError::TryFromIntError(_))));
+        assert!(matches!(convert(4), Err(VoltError::PoisonError(_))));
     }
 }
 
@@ -0,0 +1,115 @@
+use bytebuffer::ByteBuffer;
+
+use crate::{*};
+
+/// Array parameters , e.g. the ids of an `IN ?` list or a procedure taking `BIGINT[]`.
+/// On the wire they are the array type , the element type , the number of elements and the elements without their type.
+/// An empty `Vec` is an empty array , `None` sends NULL instead. `Vec<u8>` is VARBINARY , not an array.
+macro_rules! array_value {
+    ($($t:ty => $tp:expr),*) => {
+        $(
+        impl Value for Vec<$t> {
+            fn get_write_length(&self) -> i32 {
+                // the elements are written without their type byte
+                4 + self.iter().map(|v| v.get_write_length() - 1).sum::<i32>()
+            }
+
+            fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+                bytebuffer.write_i8(ARRAY_COLUMN);
+                self.marshal_in_table(bytebuffer, ARRAY_COLUMN);
+            }
+
+            fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+                bytebuffer.write_i8($tp);
+                bytebuffer.write_i16(self.len() as i16);
+                for v in self {
+                    v.marshal_in_table(bytebuffer, $tp);
+                }
+            }
+
+            fn to_value_string(&self) -> String {
+                format!("{:?}", self)
+            }
+
+            fn from_bytes(_bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
+                // tables have no array columns
+                Err(VoltError::InvalidColumnType(ARRAY_COLUMN))
+            }
+
+            fn check(&self) -> Result<(), VoltError> {
+                if self.len() > i16::MAX as usize {
+                    return Err(VoltError::ValueOutOfRange(format!("array of {} elements", self.len())));
+                }
+                self.iter().try_for_each(|v| v.check())
+            }
+        }
+
+        impl Value for Option<Vec<$t>> {
+            fn get_write_length(&self) -> i32 {
+                match self {
+                    None => 1,
+                    Some(v) => v.get_write_length(),
+                }
+            }
+
+            fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+                match self {
+                    None => bytebuffer.write_i8(NULL_COLUMN),
+                    Some(v) => v.marshal(bytebuffer),
+                }
+            }
+
+            fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, column_type: i8) {
+                if let Some(v) = self {
+                    v.marshal_in_table(bytebuffer, column_type);
+                }
+            }
+
+            fn to_value_string(&self) -> String {
+                format!("{:?}", self)
+            }
+
+            fn from_bytes(_bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
+                Err(VoltError::InvalidColumnType(ARRAY_COLUMN))
+            }
+
+            fn check(&self) -> Result<(), VoltError> {
+                self.as_ref().map_or(Ok(()), |v| v.check())
+            }
+        }
+        )*
+    };
+}
+
+array_value!(
+    i16 => SHORT_COLUMN,
+    i32 => INT_COLUMN,
+    i64 => LONG_COLUMN,
+    f64 => FLOAT_COLUMN,
+    String => STRING_COLUMN,
+    &str => STRING_COLUMN
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_params() {
+        let ids = vec![1i64, 2];
+        let names = vec!["a", "bc"];
+        let (empty, null) = (Vec::<i32>::new(), None::<Vec<i32>>);
+        let params: Vec<&dyn Value> = vec![&ids, &names, &empty, &null];
+        let mut bf = ByteBuffer::new();
+        for p in &params {
+            p.marshal(&mut bf);
+        }
+        let mut expected = vec![ARRAY_COLUMN as u8, LONG_COLUMN as u8, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2];
+        expected.extend([ARRAY_COLUMN as u8, STRING_COLUMN as u8, 0, 2, 0, 0, 0, 1, b'a', 0, 0, 0, 2, b'b', b'c']);
+        expected.extend([ARRAY_COLUMN as u8, INT_COLUMN as u8, 0, 0]);
+        expected.push(NULL_COLUMN as u8);
+        assert_eq!(params.iter().map(|p| p.get_write_length()).sum::<i32>() as usize, expected.len());
+        assert_eq!(bf.into_vec(), expected);
+        assert!(matches!(vec![0i64; i16::MAX as usize + 1].check(), Err(VoltError::ValueOutOfRange(_))));
+    }
+}
@@ -1,5 +1,6 @@
 use bigdecimal::BigDecimal;
 use bytebuffer::ByteBuffer;
+#[cfg(feature = "chrono")]
 use chrono::{DateTime, Utc};
 
 use crate::{*};
@@ -238,12 +239,15 @@ impl Value for Option<i32> {
 
 impl Value for Option<u32> {
     fn get_write_length(&self) -> i32 {
-        return 5;
+        9
     }
 
     fn marshal(&self, bytebuffer: &mut ByteBuffer) {
-        bytebuffer.write_i8(INT_COLUMN);
-        self.marshal_in_table(bytebuffer, INT_COLUMN);
+        bytebuffer.write_i8(LONG_COLUMN);
+        match self {
+            None => bytebuffer.write_bytes(&NULL_LONG_VALUE),
+            Some(v) => bytebuffer.write_i64(*v as i64),
+        }
     }
 
     fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
@@ -332,6 +336,10 @@ impl Value for Option<u64> {
         }
         return Ok(Some(u64::from_bytes(bs, _column)?));
     }
+
+    fn check(&self) -> Result<(), VoltError> {
+        self.as_ref().map_or(Ok(()), |v| v.check())
+    }
 }
 
 impl Value for Option<f64> {
@@ -367,6 +375,39 @@ impl Value for Option<f64> {
     }
 }
 
+impl Value for Option<f32> {
+    fn get_write_length(&self) -> i32 {
+        9
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(FLOAT_COLUMN);
+        self.marshal_in_table(bytebuffer, FLOAT_COLUMN);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+        match self {
+            None => {
+                bytebuffer.write_bytes(&NULL_FLOAT_VALUE)
+            }
+            Some(v) => {
+                v.marshal_in_table(bytebuffer, FLOAT_COLUMN)
+            }
+        }
+    }
+
+    fn to_value_string(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> {
+        if bs == NULL_FLOAT_VALUE {
+            return Ok(Option::None);
+        }
+        Ok(Some(f32::from_bytes(bs, _column)?))
+    }
+}
+
 
 impl Value for Option<String> {
     fn get_write_length(&self) -> i32 {
@@ -490,14 +531,7 @@ impl Value for Option<Vec<u8>> {
 
     fn marshal(&self, bytebuffer: &mut ByteBuffer) {
         bytebuffer.write_i8(VAR_BIN_COLUMN);
-        match self {
-            None => {
-                bytebuffer.write_bytes(&NULL_VARCHAR)
-            }
-            Some(v) => {
-                bytebuffer.write_bytes(&v);
-            }
-        }
+        self.marshal_in_table(bytebuffer, VAR_BIN_COLUMN);
     }
 
     fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
@@ -506,7 +540,7 @@ impl Value for Option<Vec<u8>> {
                 bytebuffer.write_bytes(&NULL_VARCHAR)
             }
             Some(v) => {
-                bytebuffer.write_bytes(&v);
+                v.marshal_in_table(bytebuffer, VAR_BIN_COLUMN)
             }
         }
     }
@@ -527,6 +561,41 @@ impl Value for Option<Vec<u8>> {
 }
 
 
+#[cfg(feature = "rust_decimal")]
+impl Value for Option<rust_decimal::Decimal> {
+    fn get_write_length(&self) -> i32 {
+        17
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(DECIMAL_COLUMN);
+        self.marshal_in_table(bytebuffer, DECIMAL_COLUMN);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+        match self {
+            None => {
+                bytebuffer.write_bytes(&NULL_DECIMAL)
+            }
+            Some(v) => {
+                v.marshal_in_table(bytebuffer, DECIMAL_COLUMN)
+            }
+        }
+    }
+
+    fn to_value_string(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn from_bytes(bs: Vec<u8>, _column: &Column) -> Result<Self, VoltError> where Self: Sized {
+        if bs == NULL_DECIMAL {
+            return Ok(Option::None);
+        }
+        Ok(Some(rust_decimal::Decimal::from_bytes(bs, _column)?))
+    }
+}
+
+#[cfg(feature = "chrono")]
 impl Value for Option<DateTime<Utc>> {
     fn get_write_length(&self) -> i32 {
         return 9;
@@ -142,8 +142,9 @@ impl VoltTable {
         if bs == NULL_FLOAT_VALUE {
             return Ok(Option::None);
         }
+        // FLOAT columns hold doubles
         let mut buffer = ByteBuffer::from_bytes(&bs);
-        let value = buffer.read_f32()?;
+        let value = narrow_f64(buffer.read_f64()?)?;
         return Ok(Some(value));
     }
 
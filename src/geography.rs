@@ -0,0 +1,120 @@
+use bytebuffer::ByteBuffer;
+
+use crate::encode::{GEOGRAPHY_POINT_COLUMN, Value, VoltError};
+use crate::table::Column;
+
+/// Both coordinates of a NULL point are 360 , which no real point has.
+const NULL_COORD: f64 = 360.0;
+
+/// A GEOGRAPHY_POINT value , in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeographyPoint {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+impl GeographyPoint {
+    pub fn new(longitude: f64, latitude: f64) -> Self {
+        GeographyPoint { longitude, latitude }
+    }
+
+    fn is_null_bytes(bs: &[u8]) -> bool {
+        let mut null = ByteBuffer::new();
+        null.write_f64(NULL_COORD);
+        null.write_f64(NULL_COORD);
+        bs == null.as_bytes()
+    }
+
+    fn write_null(bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_f64(NULL_COORD);
+        bytebuffer.write_f64(NULL_COORD);
+    }
+}
+
+impl Value for GeographyPoint {
+    fn get_write_length(&self) -> i32 {
+        17
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(GEOGRAPHY_POINT_COLUMN);
+        self.marshal_in_table(bytebuffer, GEOGRAPHY_POINT_COLUMN);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+        bytebuffer.write_f64(self.longitude);
+        bytebuffer.write_f64(self.latitude);
+    }
+
+    fn to_value_string(&self) -> String {
+        format!("POINT ({} {})", self.longitude, self.latitude)
+    }
+
+    fn from_bytes(bs: Vec<u8>, column: &Column) -> Result<Self, VoltError> {
+        if GeographyPoint::is_null_bytes(&bs) {
+            return Err(VoltError::UnexpectedNull(column.header_name.clone()));
+        }
+        let mut buffer = ByteBuffer::from_bytes(&bs);
+        let longitude = buffer.read_f64()?;
+        let latitude = buffer.read_f64()?;
+        Ok(GeographyPoint { longitude, latitude })
+    }
+}
+
+impl Value for Option<GeographyPoint> {
+    fn get_write_length(&self) -> i32 {
+        17
+    }
+
+    fn marshal(&self, bytebuffer: &mut ByteBuffer) {
+        bytebuffer.write_i8(GEOGRAPHY_POINT_COLUMN);
+        self.marshal_in_table(bytebuffer, GEOGRAPHY_POINT_COLUMN);
+    }
+
+    fn marshal_in_table(&self, bytebuffer: &mut ByteBuffer, _column_type: i8) {
+        match self {
+            None => GeographyPoint::write_null(bytebuffer),
+            Some(v) => v.marshal_in_table(bytebuffer, GEOGRAPHY_POINT_COLUMN),
+        }
+    }
+
+    fn to_value_string(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn from_bytes(bs: Vec<u8>, column: &Column) -> Result<Self, VoltError> {
+        if GeographyPoint::is_null_bytes(&bs) {
+            return Ok(None);
+        }
+        Ok(Some(GeographyPoint::from_bytes(bs, column)?))
+    }
+}
+
+/// Whether the 16 bytes of a GEOGRAPHY_POINT column are its NULL.
+pub(crate) fn is_null_point(bs: &[u8]) -> bool {
+    GeographyPoint::is_null_bytes(bs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point() {
+        let column = Column { header_name: "P".to_owned(), header_type: GEOGRAPHY_POINT_COLUMN };
+        let point = GeographyPoint::new(-71.06, 42.36);
+        let mut bs = ByteBuffer::new();
+        point.marshal(&mut bs);
+        assert_eq!(bs.len(), point.get_write_length() as usize);
+        let bs = bs.into_vec();
+        assert_eq!(bs[0], GEOGRAPHY_POINT_COLUMN as u8);
+        assert_eq!(GeographyPoint::from_bytes(bs[1..].to_vec(), &column).unwrap(), point);
+
+        let mut null = ByteBuffer::new();
+        None::<GeographyPoint>.marshal_in_table(&mut null, GEOGRAPHY_POINT_COLUMN);
+        let null = null.into_vec();
+        assert!(is_null_point(&null));
+        assert_eq!(Option::<GeographyPoint>::from_bytes(null.clone(), &column).unwrap(), None);
+        assert!(matches!(GeographyPoint::from_bytes(null, &column), Err(VoltError::UnexpectedNull(_))));
+    }
+}
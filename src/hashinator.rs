@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use bytebuffer::ByteBuffer;
+
+use crate::encode::{INT_COLUMN, LONG_COLUMN, SHORT_COLUMN, STRING_COLUMN, TINYINT_COLUMN, VAR_BIN_COLUMN};
+use crate::{Value, VoltError, VoltTable};
+
+/// Maps partitioning values to partitions the way VoltDB's elastic hashinator does:
+/// the value is hashed with MurmurHash3 and the partition owning the closest token at or below
+/// the hash wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hashinator {
+    // sorted by token
+    tokens: Vec<(i32, i32)>,
+}
+
+impl Hashinator {
+    /// Parse the elastic config , `[int: count]` followed by `[int: token, int: partition] * count`.
+    pub fn new(config: &[u8]) -> Result<Hashinator, VoltError> {
+        let mut buffer = ByteBuffer::from_bytes(config);
+        let count = buffer.read_i32()?;
+        if count <= 0 {
+            return Err(VoltError::Other(format!("Invalid hashinator token count {}", count)));
+        }
+        let mut tokens = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let token = buffer.read_i32()?;
+            let partition = buffer.read_i32()?;
+            tokens.push((token, partition));
+        }
+        tokens.sort_unstable();
+        Ok(Hashinator { tokens })
+    }
+
+    /// Build from the second table returned by `@Statistics TOPO`, holding `HASHTYPE` and `HASHCONFIG`.
+    pub fn from_topology(table: &mut VoltTable) -> Result<Hashinator, VoltError> {
+        if !table.advance_row() {
+            return Err(VoltError::NoValue("HASHCONFIG".to_owned()));
+        }
+        let hash_type = table.get_string_by_idx(0)?.unwrap_or_default();
+        if hash_type != "ELASTIC" {
+            return Err(VoltError::Other(format!("Unsupported hashinator {}", hash_type)));
+        }
+        let config = table.get_bytes_op_by_idx(1)?.ok_or_else(|| VoltError::NoValue("HASHCONFIG".to_owned()))?;
+        Hashinator::new(&config)
+    }
+
+    /// Partition for a procedure parameter, only integer, string and varbinary values can be partitioned on.
+    /// NULL values always go to partition 0.
+    pub fn partition_for_param(&self, value: &dyn Value) -> Result<i32, VoltError> {
+        let mut buffer = ByteBuffer::new();
+        value.marshal(&mut buffer);
+        let tp = buffer.read_i8()?;
+        let long = match tp {
+            TINYINT_COLUMN => Some(buffer.read_i8()? as i64).filter(|v| *v != i8::MIN as i64),
+            SHORT_COLUMN => Some(buffer.read_i16()? as i64).filter(|v| *v != i16::MIN as i64),
+            INT_COLUMN => Some(buffer.read_i32()? as i64).filter(|v| *v != i32::MIN as i64),
+            LONG_COLUMN => Some(buffer.read_i64()?).filter(|v| *v != i64::MIN),
+            STRING_COLUMN | VAR_BIN_COLUMN => {
+                let len = buffer.read_i32()?;
+                if len < 0 {
+                    return Ok(0);
+                }
+                let bytes = buffer.read_bytes(len as usize)?;
+                return Ok(self.partition_for_key(&bytes));
+            }
+            _ => return Err(VoltError::InvalidColumnType(tp)),
+        };
+        Ok(long.map_or(0, |v| self.partition_for_integer(v)))
+    }
+
+    /// Partition of a VARCHAR or VARBINARY key , hashed as its bytes , UTF-8 for a string.
+    pub fn partition_for_key(&self, key: &[u8]) -> i32 {
+        self.partition_for_token(murmur3_x64_128(key))
+    }
+
+    /// Partition of an integer key of any width , hashed as its 8 bytes in little endian order after widening.
+    pub fn partition_for_integer(&self, key: i64) -> i32 {
+        self.partition_for_key(&key.to_le_bytes())
+    }
+
+    fn partition_for_token(&self, hash: i32) -> i32 {
+        let idx = self.tokens.partition_point(|(token, _)| *token <= hash);
+        match idx {
+            // below the first token wraps around to the last one
+            0 => self.tokens[self.tokens.len() - 1].1,
+            _ => self.tokens[idx - 1].1,
+        }
+    }
+}
+
+/// A key which `@GetPartitionKeys` says lands in `partition_id`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionKey {
+    pub partition_id: i32,
+    pub key: PartitionKeyValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartitionKeyValue {
+    Integer(i64),
+    String(String),
+}
+
+impl PartitionKey {
+    /// Parse the `PARTITION_ID` , `PARTITION_KEY` table returned by `@GetPartitionKeys`.
+    pub fn from_table(table: &mut VoltTable) -> Result<Vec<PartitionKey>, VoltError> {
+        let key_type = table.get_column_by_index(1)?.header_type;
+        let mut keys = Vec::new();
+        while table.advance_row() {
+            let partition_id = table.get_i32_by_idx(0)?.ok_or_else(|| VoltError::UnexpectedNull("PARTITION_ID".to_owned()))?;
+            let key = match key_type {
+                INT_COLUMN => table.get_i32_by_idx(1)?.map(|k| PartitionKeyValue::Integer(k as i64)),
+                LONG_COLUMN => table.get_i64_by_idx(1)?.map(PartitionKeyValue::Integer),
+                STRING_COLUMN => table.get_string_by_idx(1)?.map(PartitionKeyValue::String),
+                tp => return Err(VoltError::InvalidColumnType(tp)),
+            };
+            let key = key.ok_or_else(|| VoltError::UnexpectedNull("PARTITION_KEY".to_owned()))?;
+            keys.push(PartitionKey { partition_id, key });
+        }
+        Ok(keys)
+    }
+}
+
+/// Partition id to host id of the partition leader , from the first table of `@Statistics TOPO`.
+/// The leader column looks like `hostId:siteId`.
+pub(crate) fn partition_leaders(table: &mut VoltTable) -> Result<HashMap<i32, i32>, VoltError> {
+    let mut leaders = HashMap::new();
+    while table.advance_row() {
+        let partition = match table.get_i32_by_idx(0)? {
+            None => continue,
+            Some(partition) => partition,
+        };
+        let leader = table.get_string_by_idx(2)?.unwrap_or_default();
+        let host_id = leader.split(':').next().and_then(|h| h.parse::<i32>().ok());
+        if let Some(host_id) = host_id {
+            leaders.insert(partition, host_id);
+        }
+    }
+    Ok(leaders)
+}
+
+/// Lower 32 bits of the first half of MurmurHash3 x64 128 with seed 0 , as VoltDB uses it.
+fn murmur3_x64_128(data: &[u8]) -> i32 {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+    let mut h1: u64 = 0;
+    let mut h2: u64 = 0;
+    let mut blocks = data.chunks_exact(16);
+    for block in &mut blocks {
+        let mut k1 = u64::from_le_bytes(block[0..8].try_into().unwrap());
+        let mut k2 = u64::from_le_bytes(block[8..16].try_into().unwrap());
+        k1 = k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 ^= k1;
+        h1 = h1.rotate_left(27).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x52dc_e729);
+        k2 = k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 ^= k2;
+        h2 = h2.rotate_left(31).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x3849_5ab5);
+    }
+    let tail = blocks.remainder();
+    let mut k1: u64 = 0;
+    let mut k2: u64 = 0;
+    for (i, b) in tail.iter().enumerate() {
+        if i < 8 {
+            k1 |= (*b as u64) << (8 * i);
+        } else {
+            k2 |= (*b as u64) << (8 * (i - 8));
+        }
+    }
+    if tail.len() > 8 {
+        k2 = k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 ^= k2;
+    }
+    if !tail.is_empty() {
+        k1 = k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 ^= k1;
+    }
+    h1 ^= data.len() as u64;
+    h2 ^= data.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix(h1);
+    h2 = fmix(h2);
+    h1 = h1.wrapping_add(h2);
+    h1 as i32
+}
+
+fn fmix(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^= k >> 33;
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(tokens: &[(i32, i32)]) -> Vec<u8> {
+        let mut buffer = ByteBuffer::new();
+        buffer.write_i32(tokens.len() as i32);
+        for (token, partition) in tokens {
+            buffer.write_i32(*token);
+            buffer.write_i32(*partition);
+        }
+        buffer.as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_partition_keys() {
+        let header = vec!["PARTITION_ID".to_owned(), "PARTITION_KEY".to_owned()];
+        let mut table = VoltTable::new_table(vec![INT_COLUMN, INT_COLUMN], header.clone());
+        table.add_row(crate::volt_param! {0, 14}).unwrap();
+        table.add_row(crate::volt_param! {1, 3}).unwrap();
+        table.advance_to_row(-1);
+        assert_eq!(PartitionKey::from_table(&mut table).unwrap(), vec![
+            PartitionKey { partition_id: 0, key: PartitionKeyValue::Integer(14) },
+            PartitionKey { partition_id: 1, key: PartitionKeyValue::Integer(3) },
+        ]);
+
+        let mut table = VoltTable::new_table(vec![INT_COLUMN, STRING_COLUMN], header);
+        table.add_row(crate::volt_param! {0, "a"}).unwrap();
+        table.advance_to_row(-1);
+        assert_eq!(PartitionKey::from_table(&mut table).unwrap(), vec![
+            PartitionKey { partition_id: 0, key: PartitionKeyValue::String("a".to_owned()) },
+        ]);
+    }
+
+    #[test]
+    fn test_murmur() {
+        assert_eq!(murmur3_x64_128(b""), 0);
+        // 6c1b07bc7bbc4be347939ac4a93c437a
+        assert_eq!(murmur3_x64_128(b"The quick brown fox jumps over the lazy dog"), 0xbc07_1b6c_u32 as i32);
+    }
+
+    #[test]
+    fn test_partition_for_token() {
+        let hashinator = Hashinator::new(&config(&[(i32::MIN, 0), (0, 1), (1000, 2)])).unwrap();
+        assert_eq!(hashinator.partition_for_token(i32::MIN), 0);
+        assert_eq!(hashinator.partition_for_token(-1), 0);
+        assert_eq!(hashinator.partition_for_token(0), 1);
+        assert_eq!(hashinator.partition_for_token(999), 1);
+        assert_eq!(hashinator.partition_for_token(i32::MAX), 2);
+
+        let hashinator = Hashinator::new(&config(&[(1000, 2), (0, 1)])).unwrap();
+        assert_eq!(hashinator.partition_for_token(-1), 2);
+    }
+
+    #[test]
+    fn test_partition_for_param() {
+        let hashinator = Hashinator::new(&config(&[(i32::MIN, 0), (0, 1)])).unwrap();
+        let expected = match murmur3_x64_128(&5_i64.to_le_bytes()) >= 0 {
+            true => 1,
+            false => 0,
+        };
+        assert_eq!(hashinator.partition_for_param(&5_i64).unwrap(), expected);
+        assert_eq!(hashinator.partition_for_param(&5_i32).unwrap(), expected);
+        assert_eq!(hashinator.partition_for_param(&Option::<i64>::None).unwrap(), 0);
+        assert!(hashinator.partition_for_param(&5.0_f64).is_err());
+    }
+
+    #[test]
+    fn test_partition_for_key() {
+        let hashinator = Hashinator::new(&config(&[(i32::MIN, 0), (0, 1)])).unwrap();
+        let side = |hash: i32| if hash >= 0 { 1 } else { 0 };
+        for key in ["a", "order 17", ""] {
+            assert_eq!(hashinator.partition_for_key(key.as_bytes()), side(murmur3_x64_128(key.as_bytes())));
+            assert_eq!(hashinator.partition_for_param(&key).unwrap(), hashinator.partition_for_key(key.as_bytes()));
+            assert_eq!(hashinator.partition_for_param(&key.as_bytes().to_vec()).unwrap(), hashinator.partition_for_key(key.as_bytes()));
+        }
+        for key in [-3_i64, 0, 17, i64::MAX] {
+            assert_eq!(hashinator.partition_for_integer(key), side(murmur3_x64_128(&key.to_le_bytes())));
+            assert_eq!(hashinator.partition_for_param(&key).unwrap(), hashinator.partition_for_integer(key));
+        }
+        // the integer is not hashed like its decimal string
+        let (int, string) = (murmur3_x64_128(&17_i64.to_le_bytes()), murmur3_x64_128(b"17"));
+        assert_ne!(int, string);
+    }
+}
@@ -0,0 +1,95 @@
+use serde_json::{Map, Number, Value as Json};
+
+use crate::column_value::ColumnValue;
+use crate::encode::VoltError;
+use crate::table::VoltTable;
+
+impl VoltTable {
+    /// All rows as an array of objects keyed by column name , for handing results on as JSON.
+    /// Numbers stay numbers , except DECIMAL which is a string so no digits are lost.
+    /// TIMESTAMP is an ISO-8601 string in UTC , VARBINARY and GEOGRAPHY are hex strings ,
+    /// GEOGRAPHY_POINT is its WKT and NULL of any type is `null`.
+    /// Like `deserialize_rows` it starts from the first row and leaves the cursor after the last one.
+    pub fn to_json(&mut self) -> Result<Json, VoltError> {
+        self.advance_to_row(-1);
+        let rows = self.rows().map(|row| {
+            let row = row?;
+            let mut object = Map::with_capacity(row.columns().len());
+            for (idx, column) in row.columns().iter().enumerate() {
+                object.insert(column.header_name.clone(), json_value(row.get_value(idx as i16)?));
+            }
+            Ok(Json::Object(object))
+        });
+        Ok(Json::Array(rows.collect::<Result<_, VoltError>>()?))
+    }
+}
+
+fn json_value(value: ColumnValue) -> Json {
+    match value {
+        ColumnValue::Null => Json::Null,
+        ColumnValue::TinyInt(v) => v.into(),
+        ColumnValue::SmallInt(v) => v.into(),
+        ColumnValue::Integer(v) => v.into(),
+        ColumnValue::BigInt(v) => v.into(),
+        // JSON has no NaN or infinity
+        ColumnValue::Float(v) => Number::from_f64(v).map_or(Json::Null, Json::Number),
+        ColumnValue::String(v) => v.into(),
+        ColumnValue::Timestamp(micros) => iso8601(micros).into(),
+        ColumnValue::Decimal(v) => v.to_string().into(),
+        ColumnValue::VarBinary(v) | ColumnValue::Geography(v) => v.iter().map(|b| format!("{:02x}", b)).collect::<String>().into(),
+        ColumnValue::GeographyPoint(v) => format!("POINT ({} {})", v.longitude, v.latitude).into(),
+    }
+}
+
+/// Microseconds since the epoch as `YYYY-MM-DDTHH:MM:SS.ffffffZ` , without needing the `chrono` feature.
+fn iso8601(micros: i64) -> String {
+    let secs = micros.div_euclid(1_000_000);
+    let days = secs.div_euclid(86_400);
+    let time = secs.rem_euclid(86_400);
+    // days to the civil date , after Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z", year, month, day,
+            time / 3600, time % 3600 / 60, time % 60, micros.rem_euclid(1_000_000))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::encode::{DECIMAL_COLUMN, FLOAT_COLUMN, LONG_COLUMN, STRING_COLUMN, TIMESTAMP_COLUMN, Value};
+    use crate::volt_param;
+    use crate::BigDecimal;
+
+    use super::*;
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00.000000Z");
+        assert_eq!(iso8601(951_782_400_000_000 + 1_500_000), "2000-02-29T00:00:01.500000Z");
+        assert_eq!(iso8601(-1), "1969-12-31T23:59:59.999999Z");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let header = ["ID", "NAME", "PRICE", "RATIO", "AT"].iter().map(|c| c.to_string()).collect();
+        let types = vec![LONG_COLUMN, STRING_COLUMN, DECIMAL_COLUMN, FLOAT_COLUMN, TIMESTAMP_COLUMN];
+        let mut table = VoltTable::new_table(types, header);
+        let (id, name, price, ratio, at) = (1i64, "one", BigDecimal::from(5), 0.5f64, 0i64);
+        let (none_name, none_price, none_ratio) = (None::<String>, None::<BigDecimal>, None::<f64>);
+        table.add_row(volt_param!(id, name, price, ratio, at)).unwrap();
+        table.add_row(volt_param!(id, none_name, none_price, none_ratio, at)).unwrap();
+        let json = table.to_json().unwrap();
+        assert_eq!(json, json!([
+            {"ID": 1, "NAME": "one", "PRICE": "5.000000000000", "RATIO": 0.5, "AT": "1970-01-01T00:00:00.000000Z"},
+            {"ID": 1, "NAME": null, "PRICE": null, "RATIO": null, "AT": "1970-01-01T00:00:00.000000Z"},
+        ]));
+    }
+}
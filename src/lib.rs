@@ -52,7 +52,7 @@
 //!    }
 //!
 //!    let hosts = vec![IpPort::new("localhost".to_string(), 21211)];
-//!    let mut pool = Pool::new(Opts::new(hosts)).unwrap();
+//!    let pool = Pool::new(Opts::new(hosts)).unwrap();
 //!
 //!    let mut node = pool.get_conn()?;
 //!    // Create table if not exists.
@@ -160,6 +160,38 @@
 #![crate_name = "voltdb_client_rust"]
 #![crate_type = "rlib"]
 #![crate_type = "dylib"]
+// the variants of `VoltError` nest `quick_error!` deeper than the default limit
+#![recursion_limit = "256"]
+
+// lets the derive macros name this crate from inside it too
+extern crate self as voltdb_client_rust;
+
+// Errors nobody waits for , e.g. of the listener thread , and responses nobody waits for any more. They go to the `log` crate with the `log` feature
+// and nowhere otherwise , the callers still see them as failed requests.
+#[cfg(feature = "log")]
+macro_rules! log_warn {
+    ($($arg:tt)*) => { log::warn!($($arg)*) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_warn {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } };
+}
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { log::debug!($($arg)*) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } };
+}
+#[cfg(feature = "log")]
+macro_rules! log_error {
+    ($($arg:tt)*) => { log::error!($($arg)*) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_error {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } };
+}
 
 pub mod table;
 pub mod node;
@@ -168,8 +200,29 @@ mod procedure_invocation;
 mod response;
 mod generate;
 mod pool;
+mod client;
+mod hashinator;
 mod encode_option;
+mod encode_array;
+mod stream;
+mod row;
+mod geography;
+mod retry;
+mod bulk_loader;
+mod metrics;
+mod column_value;
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "tokio")]
+mod async_node;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testutil;
+#[cfg(test)]
+mod mock_server;
 
+#[cfg(feature = "chrono")]
 pub use chrono;
 pub use bigdecimal;
 
@@ -177,8 +230,24 @@ pub use crate::node::{*};
 pub use crate::table::{*};
 pub use crate::encode::{*};
 pub use crate::pool::{*};
+pub use crate::client::{*};
+pub use crate::hashinator::{Hashinator, PartitionKey, PartitionKeyValue};
+pub use crate::row::{Row, Rows};
+pub use crate::geography::GeographyPoint;
+pub use crate::retry::{is_transient, RetryPolicy};
+pub use crate::bulk_loader::{BulkLoader, RowFailure};
+pub use crate::metrics::MetricsSnapshot;
+pub use crate::column_value::ColumnValue;
+pub use crate::response::{ResponseStatus, VoltResponseInfo};
+#[cfg(feature = "tokio")]
+pub use crate::async_node::AsyncNode;
 pub use crate::bigdecimal::BigDecimal;
+#[cfg(feature = "chrono")]
 pub use crate::chrono::{DateTime, Utc};
+#[cfg(feature = "rust_decimal")]
+pub use rust_decimal::Decimal;
+#[cfg(feature = "derive")]
+pub use voltdb_client_rust_derive::VoltParams;
 
 #[macro_export]
 macro_rules! volt_param {
@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Counters of one node , updated by the callers and the listener without locking.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    requests: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    errors: AtomicU64,
+    in_flight_peak: AtomicUsize,
+}
+
+impl Metrics {
+    /// A call of `bytes` was sent while `in_flight` were waiting , itself included.
+    pub(crate) fn sent(&self, bytes: usize, in_flight: usize) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.in_flight_peak.fetch_max(in_flight, Ordering::Relaxed);
+    }
+
+    pub(crate) fn received(&self, bytes: usize) {
+        self.bytes_received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            in_flight_peak: self.in_flight_peak.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counters of a node since it was created , from `Node::metrics`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct MetricsSnapshot {
+    /// Calls sent.
+    pub requests: u64,
+    /// Bytes of the calls sent.
+    pub bytes_sent: u64,
+    /// Bytes of all messages received , including length prefixes and keepalive answers.
+    pub bytes_received: u64,
+    /// Calls which could not be sent or were answered with a failure.
+    pub errors: u64,
+    /// Most calls waiting for a response at the same time.
+    pub in_flight_peak: usize,
+}
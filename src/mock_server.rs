@@ -0,0 +1,104 @@
+//! Fake server speaking just enough of the wire protocol for the tests.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use bytebuffer::ByteBuffer;
+
+use crate::encode::{INT_COLUMN, STRING_COLUMN, Value, VAR_BIN_COLUMN};
+use crate::node::IpPort;
+use crate::table::VoltTable;
+use crate::volt_param;
+pub(crate) use crate::testutil::{error_response, frame, tables_response};
+use crate::testutil::{login_response, read_frame};
+
+/// Response to `handle` with a table of one INTEGER column `A` holding `value`.
+pub(crate) fn int_response(handle: i64, value: i32) -> Vec<u8> {
+    let mut res = ByteBuffer::new();
+    res.write_u8(0);
+    res.write_i64(handle);
+    res.write_u8(0);
+    res.write_i8(1);
+    res.write_i8(-128);
+    res.write_i32(0);
+    res.write_i16(1);
+    res.write_i32(0);
+    res.write_i32(0);
+    res.write_i8(0);
+    res.write_i16(1);
+    res.write_i8(INT_COLUMN);
+    res.write_string("A");
+    res.write_i32(1);
+    res.write_i32(4);
+    res.write_i32(value);
+    frame(res)
+}
+
+/// Read a procedure call , returning its name and handle.
+pub(crate) fn read_call(stream: &mut TcpStream) -> (String, i64) {
+    try_read_call(stream).unwrap()
+}
+
+/// Like `read_call`, but `None` once the client closed the connection.
+pub(crate) fn try_read_call(stream: &mut TcpStream) -> Option<(String, i64)> {
+    let (name, handle, _) = try_read_call_with_params(stream)?;
+    Some((name, handle))
+}
+
+/// Like `read_call`, also returning the parameters starting with their count.
+pub(crate) fn read_call_with_params(stream: &mut TcpStream) -> (String, i64, ByteBuffer) {
+    try_read_call_with_params(stream).unwrap()
+}
+
+fn try_read_call_with_params(stream: &mut TcpStream) -> Option<(String, i64, ByteBuffer)> {
+    let mut call = read_frame(stream)?;
+    let _version = call.read_u8().unwrap();
+    let name = call.read_string().unwrap();
+    let handle = call.read_i64().unwrap();
+    Some((name, handle, call))
+}
+
+/// Answer every call with `value` until the client closes the connection.
+pub(crate) fn answer_all(stream: &mut TcpStream, value: i32) {
+    while let Some((_, handle)) = try_read_call(stream) {
+        if stream.write_all(&int_response(handle, value)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Keep the connection open until the client closes it.
+pub(crate) fn drain(stream: &mut TcpStream) {
+    let _ = stream.read_to_end(&mut Vec::new());
+}
+
+/// Fake server , logs every client in and hands the connection to `handler`, one connection after the other.
+pub(crate) fn serve<F>(handler: F) -> IpPort where F: Fn(TcpStream) + Send + 'static {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            read_frame(&mut stream);
+            stream.write_all(&login_response()).unwrap();
+            handler(stream);
+        }
+    });
+    IpPort::new("127.0.0.1".to_string(), port)
+}
+
+/// Tables of `@Statistics TOPO` for one partition led by host 1 , with the hash config as the extra table.
+pub(crate) fn topology_tables() -> Vec<VoltTable> {
+    let header = ["Partition", "Sites", "Leader"].iter().map(|c| c.to_string()).collect();
+    let mut partitions = VoltTable::new_table(vec![INT_COLUMN, STRING_COLUMN, STRING_COLUMN], header);
+    partitions.add_row(volt_param!(0, "1:0", "1:0")).unwrap();
+    let mut config = ByteBuffer::new();
+    config.write_i32(1);
+    config.write_i32(i32::MIN);
+    config.write_i32(0);
+    let config = config.into_vec();
+    let mut hash = VoltTable::new_table(vec![STRING_COLUMN, VAR_BIN_COLUMN], vec!["HASHTYPE".to_owned(), "HASHCONFIG".to_owned()]);
+    hash.add_row(volt_param!("ELASTIC", config)).unwrap();
+    vec![partitions, hash]
+}
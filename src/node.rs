@@ -1,24 +1,63 @@
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::io::{Read, Write};
-use std::net::{Ipv4Addr, Shutdown, TcpStream};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
 use std::str::{from_utf8, FromStr};
-use std::sync::{Arc, mpsc, Mutex, RwLock};
-use std::sync::atomic::{AtomicI64, Ordering};
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, mpsc, Mutex, MutexGuard, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use bytebuffer::ByteBuffer;
 use byteorder::{BigEndian, ReadBytesExt};
+use sha1::Sha1;
 use sha2::{Digest, Sha256};
 
+use crate::client::LoadBalanceStrategy;
 use crate::encode::{Value, VoltError};
-use crate::procedure_invocation::new_procedure_invocation;
+use crate::hashinator::PartitionKey;
+use crate::metrics::{Metrics, MetricsSnapshot};
+use crate::procedure_invocation::{check_params, new_procedure_invocation};
 use crate::response::VoltResponseInfo;
+use crate::retry::RetryPolicy;
+use crate::stream::{self, Stream};
 use crate::table::{new_volt_table, VoltTable};
 use crate::volt_param;
 
-const PING_HANDLE: i64 = 1 << 63 - 1;
+/// Handle of the pings , `next_handle` never hands it out for a request.
+pub(crate) const PING_HANDLE: i64 = i64::MIN;
+/// Handle the server pushes topology updates with once subscribed , see `NodeOpt::on_topology_change`.
+pub(crate) const TOPOLOGY_HANDLE: i64 = i64::MAX - 1;
+
+/// Next request handle from `counter` , skipping `PING_HANDLE` and `TOPOLOGY_HANDLE`.
+pub(crate) fn next_handle(counter: &AtomicI64) -> i64 {
+    loop {
+        let handle = counter.fetch_add(1, Ordering::Relaxed);
+        if handle != PING_HANDLE && handle != TOPOLOGY_HANDLE {
+            return handle;
+        }
+    }
+}
+
+/// Connect timeout used by `get_node`.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pause between two reconnect attempts used by `get_node`.
+pub const DEFAULT_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// Port servers listen on for client connections.
+pub const DEFAULT_CLIENT_PORT: u16 = 21212;
+/// Port servers listen on for admin connections , the only ones allowed to write while the cluster is paused.
+pub const DEFAULT_ADMIN_PORT: u16 = 21211;
+/// Largest response accepted by default , 50 MiB like the message limit of the server.
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 50 << 20;
+/// Largest response buffer the listener keeps for the next response , 1 MiB.
+pub const DEFAULT_MAX_REUSED_BUFFER: usize = 1 << 20;
+/// How often the keepalive thread checks whether the node was shut down.
+const KEEPALIVE_POLL: Duration = Duration::from_millis(100);
+/// How often `shutdown_graceful` checks whether all requests were answered.
+const DRAIN_POLL: Duration = Duration::from_millis(10);
 
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -26,8 +65,8 @@ pub struct Opts(pub(crate) Box<InnerOpts>);
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct IpPort {
-    ip_host: String,
-    port: u16,
+    pub(crate) ip_host: String,
+    pub(crate) port: u16,
 }
 
 impl IpPort {
@@ -47,10 +86,63 @@ impl Opts {
                 ip_ports: hosts,
                 user: None,
                 pass: None,
+                admin_port: None,
+                load_balance: LoadBalanceStrategy::RoundRobin,
             })
         };
         opt
     }
+
+    /// User to log in as on every host.
+    pub fn with_user(mut self, user: String) -> Opts {
+        self.0.user = Some(user);
+        self
+    }
+
+    /// Password to log in with on every host.
+    pub fn with_password(mut self, pass: String) -> Opts {
+        self.0.pass = Some(pass);
+        self
+    }
+
+    /// Port of the admin connection `Client` opens for admin procedures like `@Pause` , usually `DEFAULT_ADMIN_PORT`.
+    pub fn with_admin_port(mut self, port: u16) -> Opts {
+        self.0.admin_port = Some(port);
+        self
+    }
+
+    /// How `Client` picks the host for a call , round-robin by default.
+    pub fn with_load_balance(mut self, strategy: LoadBalanceStrategy) -> Opts {
+        self.0.load_balance = strategy;
+        self
+    }
+
+    /// Connect to `host` as well.
+    pub fn add_host(mut self, host: IpPort) -> Opts {
+        self.0.ip_ports.push(host);
+        self
+    }
+
+    /// Connection settings for the `i`-th host.
+    pub(crate) fn to_node_opt(&self, i: usize) -> Option<NodeOpt> {
+        let ip_port = self.0.ip_ports.get(i).cloned()?;
+        Some(NodeOpt {
+            pass: self.0.pass.clone(),
+            user: self.0.user.clone(),
+            ..NodeOpt::new(ip_port)
+        })
+    }
+
+    /// Settings of an admin connection to the `i`-th host , `None` without an admin port.
+    pub(crate) fn to_admin_node_opt(&self, i: usize) -> Option<NodeOpt> {
+        let port = self.0.admin_port?;
+        let opt = self.to_node_opt(i)?;
+        Some(NodeOpt {
+            ip_port: IpPort::new(opt.ip_port.ip_host.clone(), port),
+            role: PortRole::Admin,
+            ..opt
+        })
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -58,40 +150,343 @@ pub(crate) struct InnerOpts {
     pub(crate) ip_ports: Vec<IpPort>,
     pub(crate) user: Option<String>,
     pub(crate) pass: Option<String>,
+    pub(crate) admin_port: Option<u16>,
+    pub(crate) load_balance: LoadBalanceStrategy,
 }
 
 
+#[derive(Clone)]
 pub struct NodeOpt {
     pub ip_port: IpPort,
     pub user: Option<String>,
     pub pass: Option<String>,
+    /// Give up connecting after this long, `None` waits as long as the OS does.
+    pub connect_timeout: Option<Duration>,
+    /// Give up logging in after the connection was opened when the server has not answered for this long ,
+    /// with `VoltError::LoginTimeout`. `None` uses `read_timeout`.
+    pub login_timeout: Option<Duration>,
+    /// Read timeout of the socket, requests waiting longer than this fail with `VoltError::Timeout`.
+    pub read_timeout: Option<Duration>,
+    /// Write timeout of the socket.
+    pub write_timeout: Option<Duration>,
+    /// Log in again when the connection drops, instead of leaving the node unusable.
+    pub auto_reconnect: bool,
+    /// Pause between two reconnect attempts.
+    pub reconnect_interval: Duration,
+    /// Send a `@Ping` this often so idle connections are not dropped, `None` disables it.
+    pub keepalive_interval: Option<Duration>,
+    /// Encrypt the connection , needs the `tls` feature.
+    pub tls: Option<TlsConfig>,
+    /// Digest of the password sent when logging in.
+    pub hash_scheme: HashScheme,
+    /// SHA-256 digest of the password , sent as is instead of hashing `pass` so the plaintext need not be known.
+    /// Takes precedence over `pass` and `hash_scheme`.
+    pub pass_hash: Option<[u8; 32]>,
+    /// Calls fail with `VoltError::BackpressureExceeded` while this many are waiting for a response,
+    /// instead of queueing without limit. `None` is unlimited.
+    pub max_outstanding: Option<usize>,
+    /// Refuse to connect to servers older than this `(major, minor, patch)` , or whose version is not known,
+    /// with `VoltError::UnsupportedServerVersion`.
+    pub min_version: Option<(u32, u32, u32)>,
+    /// Report a response which can not be decoded as `VoltError::DecodeError` with its bytes in hex , for bug reports.
+    /// Off by default , as the response may hold sensitive data.
+    pub capture_raw: bool,
+    /// Disable Nagle's algorithm so a call goes out at once , `true` by default.
+    /// Turn it off to let the OS coalesce the small writes of many pipelined calls.
+    pub nodelay: bool,
+    /// The listener reads every response into one buffer , which is kept for the next response up to this many bytes.
+    /// A larger response gets its own buffer , freed once it is decoded. 0 allocates for every response.
+    pub max_reused_buffer: usize,
+    /// A response whose length prefix says it is longer than this fails with `VoltError::ResponseTooLarge`
+    /// and closes the connection , instead of allocating whatever a broken or spoofed server announces.
+    pub max_response_size: usize,
+    /// Which port of the server `ip_port` is , it must match as the handshake does not tell.
+    pub role: PortRole,
+    /// Subscribe to topology updates whenever the node logs in and run this with each one , on the listener thread.
+    /// Without it the server sends none.
+    pub on_topology_change: Option<TopologyCallback>,
+}
+
+/// Kind of port a node is connected to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum PortRole {
+    /// The port for applications , `DEFAULT_CLIENT_PORT` unless configured otherwise.
+    #[default]
+    Client,
+    /// The port for administration , `DEFAULT_ADMIN_PORT` unless configured otherwise.
+    /// It stays writable while the cluster is paused , so `resume` only works over it.
+    Admin,
+}
 
+/// Password digest used in the login handshake , it must match the one the cluster is configured for.
+/// The server does not say why a login was rejected, so a mismatch shows up as `VoltError::AuthFailed`.
+/// A closed set , as the login message announces the scheme with a byte the server must know.
+/// To log in with a digest computed elsewhere , e.g. a fixed one in tests , set `NodeOpt::pass_hash`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum HashScheme {
+    /// For older clusters.
+    Sha1,
+    #[default]
+    Sha256,
 }
 
+impl HashScheme {
+    /// Byte announcing the scheme in the login message.
+    fn service_byte(self) -> u8 {
+        match self {
+            HashScheme::Sha1 => 0,
+            HashScheme::Sha256 => 1,
+        }
+    }
+
+    fn digest(self, password: &[u8]) -> Vec<u8> {
+        match self {
+            HashScheme::Sha1 => Sha1::digest(password).to_vec(),
+            HashScheme::Sha256 => Sha256::digest(password).to_vec(),
+        }
+    }
+}
+
+impl NodeOpt {
+    /// Settings for `ip_port` without credentials, timeouts, reconnecting or keepalive.
+    /// Set the other fields with struct update syntax , `NodeOpt { read_timeout: .., ..NodeOpt::new(ip_port) }`.
+    pub fn new(ip_port: IpPort) -> NodeOpt {
+        NodeOpt {
+            ip_port,
+            user: None,
+            pass: None,
+            connect_timeout: None,
+            login_timeout: None,
+            read_timeout: None,
+            write_timeout: None,
+            auto_reconnect: false,
+            reconnect_interval: DEFAULT_RECONNECT_INTERVAL,
+            keepalive_interval: None,
+            tls: None,
+            hash_scheme: HashScheme::default(),
+            pass_hash: None,
+            max_outstanding: None,
+            min_version: None,
+            capture_raw: false,
+            nodelay: true,
+            max_reused_buffer: DEFAULT_MAX_REUSED_BUFFER,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            role: PortRole::default(),
+            on_topology_change: None,
+        }
+    }
+}
+
+/// TLS settings of a connection.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TlsConfig {
+    /// PEM file with the CA certificates the server certificate is checked against.
+    pub ca_cert_path: PathBuf,
+    /// Name the server certificate must be valid for , defaults to the host of `ip_port`.
+    pub sni_hostname: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn new(ca_cert_path: PathBuf, sni_hostname: Option<String>) -> Self {
+        TlsConfig {
+            ca_cert_path,
+            sni_hostname,
+        }
+    }
+}
+
+
+/// Called with a topology update pushed by the server , shaped like the result of `@Statistics TOPO`:
+/// a row per partition with its leader , and the hashinator config as the first extra table.
+/// Hosts joining or leaving the cluster change the partition leaders.
+pub type TopologyCallback = Arc<dyn Fn(VoltTable) + Send + Sync>;
+
+/// Called with the response of `Node::call_sp_with_callback`.
+pub type Callback = Box<dyn FnOnce(Result<VoltTable, VoltError>) + Send>;
+
+/// Where the response of a request goes.
+enum Responder {
+    Channel(Mutex<Sender<Result<VoltTable, VoltError>>>),
+    // behind a mutex only to be `Sync` , it is taken out by value
+    Callback(Mutex<Callback>),
+}
+
+impl Debug for Responder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Responder::Channel(_) => write!(f, "Channel"),
+            Responder::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
+
+impl Responder {
+    fn channel() -> (Responder, Receiver<Result<VoltTable, VoltError>>) {
+        let (tx, rx) = mpsc::channel();
+        (Responder::Channel(Mutex::new(tx)), rx)
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct NetworkRequest {
     handle: i64,
-    query: bool,
-    sync: bool,
-    num_bytes: i32,
-    channel: Mutex<Sender<VoltTable>>,
+    created: Instant,
+    responder: Responder,
+    // only recorded into with the `tracing` feature , held until the response either way
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    span: CallSpan,
+}
+
+/// Span of a call from sending it until its response , nothing without the `tracing` feature.
+#[cfg(feature = "tracing")]
+type CallSpan = tracing::Span;
+#[cfg(not(feature = "tracing"))]
+type CallSpan = ();
+
+#[cfg(feature = "tracing")]
+fn call_span(procedure: &str, params: usize, handle: i64) -> CallSpan {
+    tracing::info_span!("call_sp", procedure, params, handle, latency_us = tracing::field::Empty, status = tracing::field::Empty)
+}
+
+#[cfg(not(feature = "tracing"))]
+fn call_span(_procedure: &str, _params: usize, _handle: i64) -> CallSpan {}
+
+impl NetworkRequest {
+    /// Hand over the response , after `server_result` for one from the server.
+    fn respond(self, res: Result<VoltTable, VoltError>) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("latency_us", self.created.elapsed().as_micros() as u64);
+            match &res {
+                Ok(_) => self.span.record("status", "ok"),
+                Err(err) => self.span.record("status", tracing::field::display(err)),
+            };
+        }
+        match self.responder {
+            Responder::Channel(channel) => {
+                if let Ok(sender) = channel.into_inner() {
+                    // the caller gave up waiting , like after `Node::cancel`
+                    if sender.send(res).is_err() {
+                        log_debug!("the receiver of call {} is gone , dropping its response", self.handle);
+                    }
+                }
+            }
+            Responder::Callback(callback) => {
+                let callback = match callback.into_inner() {
+                    Ok(callback) => callback,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+                // the listener runs it , a panic must not take the connection down with it
+                if panic::catch_unwind(AssertUnwindSafe(|| callback(res))).is_err() {
+                    log_error!("the callback of call {} panicked", self.handle);
+                }
+            }
+        }
+    }
+}
+
+/// The response , or the failure the server reported in it as the `VoltError` of `has_error`.
+fn server_result(res: Result<VoltTable, VoltError>) -> Result<VoltTable, VoltError> {
+    res.and_then(|mut table| match table.has_error() {
+        None => Ok(table),
+        Some(err) => Err(err),
+    })
+}
+
+/// Splits the socket into length prefixed frames.
+/// A read timeout in the middle of a frame keeps what was read so far , so the next read continues where it stopped.
+/// The buffer of a frame handed back with `recycle` is reused for the next one , unless it grew beyond `keep` bytes.
+struct FrameReader {
+    prefix: [u8; 4],
+    frame: Vec<u8>,
+    filled: usize,
+    in_frame: bool,
+    keep: usize,
+    max: usize,
+}
+
+impl FrameReader {
+    fn new(keep: usize, max: usize) -> Self {
+        FrameReader {
+            prefix: [0; 4],
+            frame: vec![],
+            filled: 0,
+            in_frame: false,
+            keep,
+            max,
+        }
+    }
+
+    /// Next complete frame without its length prefix, `VoltError::Timeout` when the socket timed out before that.
+    fn read_frame(&mut self, tcp: &mut dyn Read) -> Result<Vec<u8>, VoltError> {
+        loop {
+            let buf = match self.in_frame {
+                true => &mut self.frame[..],
+                false => &mut self.prefix[..],
+            };
+            while self.filled < buf.len() {
+                match tcp.read(&mut buf[self.filled..]) {
+                    Ok(0) => return Err(VoltError::Io(std::io::ErrorKind::UnexpectedEof.into())),
+                    Ok(n) => self.filled += n,
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {
+                        return Err(VoltError::Timeout);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(VoltError::Io(e)),
+                }
+            }
+            self.filled = 0;
+            if self.in_frame {
+                self.in_frame = false;
+                return Ok(std::mem::take(&mut self.frame));
+            }
+            // zeroing the reused capacity is cheaper than allocating per response
+            self.frame.clear();
+            self.frame.resize(response_size(u32::from_be_bytes(self.prefix), self.max)?, 0);
+            self.in_frame = true;
+        }
+    }
+
+    /// Hand back the buffer of a frame once it was decoded.
+    fn recycle(&mut self, frame: Vec<u8>) {
+        if frame.capacity() <= self.keep {
+            self.frame = frame;
+        }
+    }
 }
 
 pub trait Connection: Sync + Send + 'static {}
 
+/// Connection to one server.
+/// Calls take `&self` , so a node can be shared between threads in an `Arc` with the responses sorted out by the listener.
+/// Every call is flushed to the socket as soon as it is written , batching them is left to the caller.
 #[allow(dead_code)]
 pub struct Node {
-    tcp_stream: Box<Option<TcpStream>>,
-    info: ConnInfo,
+    opt: NodeOpt,
+    tcp_stream: Arc<Mutex<Option<Box<dyn Stream>>>>,
+    info: Arc<RwLock<ConnInfo>>,
     requests: Arc<RwLock<HashMap<i64, NetworkRequest>>>,
     stop: Arc<Mutex<bool>>,
+    // kept across reconnects , a handle reused on the new connection could be matched with a late response to the old one
     counter: Mutex<AtomicI64>,
+    // set by `shutdown_graceful` to refuse new requests
+    closing: AtomicBool,
+    // set by the listener when the connection failed and is not recovered
+    broken: Arc<AtomicBool>,
+    metrics: Arc<Metrics>,
 }
 
 impl Debug for Node {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        return write!(f, "Pending request: {}", 1);
+        let info = self.conn_info();
+        f.debug_struct("Node")
+            .field("host", &format_args!("{}:{}", self.opt.ip_port.ip_host, self.opt.ip_port.port))
+            .field("connection_id", &info.connection_id())
+            .field("build", &info.build_string())
+            .field("role", &self.opt.role)
+            .field("connected", &self.is_connected())
+            .field("pending_requests", &self.pending_requests())
+            .finish()
     }
 }
 
@@ -101,7 +496,7 @@ impl Drop for Node {
         match res {
             Ok(_) => {}
             Err(e) => {
-                eprintln!("{:?}", e);
+                log_warn!("closing the connection failed: {}", e);
             }
         }
     }
@@ -113,218 +508,671 @@ impl Connection for Node {}
 
 impl Node {
     pub fn new(opt: NodeOpt) -> Result<Node, VoltError> {
-        let ip_host = opt.ip_port;
-        let addr = format!("{}:{}", ip_host.ip_host, ip_host.port);
-        let mut buffer = ByteBuffer::new();
-        let result = [1; 1];
-        buffer.write_u32(0);
-        buffer.write_bytes(&result);
-        buffer.write_bytes(&result);
-        buffer.write_string("database");
-        match opt.user {
-            None => {
-                buffer.write_string("");
-            }
-            Some(user) => {
-                buffer.write_string(user.as_str());
-            }
-        }
-        match opt.pass {
-            None => {
-                let password = [];
-                let mut hasher: Sha256 = Sha256::new();
-                Digest::update(&mut hasher, password);
-                buffer.write_bytes(&hasher.finalize());
-            }
-            Some(password) => {
-                let password = password.as_bytes();
-                let mut hasher: Sha256 = Sha256::new();
-                Digest::update(&mut hasher, password);
-                buffer.write_bytes(&hasher.finalize());
-            }
-        }
+        let (stream, info) = Node::connect(&opt)?;
+        Node::start(opt, stream, info)
+    }
 
-        buffer.set_wpos(0);
-        buffer.write_u32((buffer.len() - 4) as u32);
-        let bs = buffer.as_bytes();
-        let mut stream: TcpStream = TcpStream::connect(addr)?;
-        stream.write_all(bs)?;
-        stream.flush()?;
-        let read = stream.read_u32::<BigEndian>()?;
-        let mut all = vec![0; read as usize];
-        stream.read_exact(&mut all)?;
-        let mut res = ByteBuffer::from_bytes(&all);
-        let _version = res.read_u8()?;
-        let auth = res.read_u8()?;
-        if auth != 0 {
-            return Err(VoltError::AuthFailed);
-        }
-        let host_id = res.read_i32()?;
-        let connection = res.read_i64()?;
-        let _ = res.read_i64()?;
-        let leader = res.read_i32()?;
-        let bs = (leader as u32).to_be_bytes();
-        let leader_addr = Ipv4Addr::from(bs);
-        // TODO check IP
-        let length = res.read_i32()?;
-        let mut build = vec![0; length as usize];
-        res.read_exact(&mut build)?;
-        let b = from_utf8(&build)?;
-        let info = ConnInfo {
-            host_id,
-            connection,
-            leader_addr,
-            build: String::from(b),
-        };
+    /// Log in over `stream` , connected by the caller , e.g. through a proxy or to a test server.
+    /// `opt.ip_port` only names the host for TLS and `Debug` , but `reconnect` and `auto_reconnect` connect to it themselves.
+    pub fn from_stream(stream: TcpStream, opt: NodeOpt) -> Result<Node, VoltError> {
+        let (stream, info) = Node::login(stream, &opt)?;
+        Node::start(opt, stream, info)
+    }
+
+    /// Start the listener and keepalive of a logged in connection.
+    fn start(opt: NodeOpt, stream: Box<dyn Stream>, info: ConnInfo) -> Result<Node, VoltError> {
         let data = Arc::new(RwLock::new(HashMap::new()));
         let mut res = Node {
+            opt,
             stop: Arc::new(Mutex::new(false)),
-            tcp_stream: Box::new(Option::Some(stream)),
-            info,
+            tcp_stream: Arc::new(Mutex::new(Option::Some(stream))),
+            info: Arc::new(RwLock::new(info)),
             requests: data,
             counter: Mutex::new(AtomicI64::new(1)),
+            closing: AtomicBool::new(false),
+            broken: Arc::new(AtomicBool::new(false)),
+            metrics: Arc::new(Metrics::default()),
         };
         res.listen()?;
+        res.keepalive();
+        Node::subscribe(&res.opt, &res.tcp_stream)?;
         return Ok(res);
     }
+
+    /// Open the socket and log in.
+    fn connect(opt: &NodeOpt) -> Result<(Box<dyn Stream>, ConnInfo), VoltError> {
+        let ip_host = &opt.ip_port;
+        let addr = format!("{}:{}", ip_host.ip_host, ip_host.port);
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(VoltError::Other(format!("can not resolve {}", addr)));
+        }
+        let socket = connect_any(&addrs, opt.connect_timeout)?;
+        Node::login(socket, opt)
+    }
+
+    /// Log in over the connected `socket` , wrapping it for TLS first if configured.
+    fn login(socket: TcpStream, opt: &NodeOpt) -> Result<(Box<dyn Stream>, ConnInfo), VoltError> {
+        // a socket option , so it holds for the handle the listener reads from as well
+        socket.set_nodelay(opt.nodelay)?;
+        let login_timeout = opt.login_timeout.or(opt.read_timeout);
+        socket.set_read_timeout(login_timeout)?;
+        socket.set_write_timeout(login_timeout)?;
+        let handle = socket.try_clone()?;
+        let login = || -> Result<(Box<dyn Stream>, Vec<u8>), VoltError> {
+            let mut stream = stream::wrap(socket, &opt.ip_port.ip_host, opt.tls.as_ref())?;
+            stream.write_all(&login_message(opt))?;
+            stream.flush()?;
+            let read = response_size(stream.read_u32::<BigEndian>()?, opt.max_response_size)?;
+            let mut all = vec![0; read];
+            stream.read_exact(&mut all)?;
+            Ok((stream, all))
+        };
+        let (stream, all) = login().map_err(|e| match e {
+            VoltError::Io(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => VoltError::LoginTimeout,
+            e => e,
+        })?;
+        handle.set_read_timeout(opt.read_timeout)?;
+        handle.set_write_timeout(opt.write_timeout)?;
+        let info = parse_login_response(&all)?;
+        check_version(opt, &info)?;
+        Ok((stream, info))
+    }
+
+    /// Log in again against the same host and restart the listener.
+    /// Requests still waiting for the old connection fail with `VoltError::ConnectionReset`.
+    pub fn reconnect(&mut self) -> Result<(), VoltError> {
+        self.shutdown()?;
+        Node::fail_pending(&self.requests)?;
+        let (stream, info) = Node::connect(&self.opt)?;
+        *self.tcp_stream.lock()? = Some(stream);
+        *self.info.write()? = info;
+        self.stop = Arc::new(Mutex::new(false));
+        self.closing.store(false, Ordering::Relaxed);
+        self.broken.store(false, Ordering::Relaxed);
+        self.listen()?;
+        self.keepalive();
+        Node::subscribe(&self.opt, &self.tcp_stream)
+    }
+
+    /// Kind of port the node is connected to , as set in `NodeOpt::role`.
+    pub fn role(&self) -> PortRole {
+        self.opt.role
+    }
+
+    /// Whether the connection failed and is not coming back by itself , `reconnect` makes the node usable again.
+    pub fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::Relaxed)
+    }
+
+    /// Whether there is a connection with a listener reading from it , it may still have died without the listener noticing yet.
+    /// `health_check` asks the server.
+    pub fn is_connected(&self) -> bool {
+        let stopped = self.stop.lock().map(|stop| *stop).unwrap_or(true);
+        let open = self.tcp_stream.lock().map(|stream| stream.is_some()).unwrap_or(false);
+        open && !stopped && !self.is_broken()
+    }
+
+    /// Like `ping`, but waits only up to `timeout` for the answer , `VoltError::Timeout` if none came.
+    pub fn health_check(&self, timeout: Duration) -> Result<(), VoltError> {
+        let rx = self.call_sp_timeout("@Ping", volt_param!(), timeout)?;
+        block_for_result_timeout(&rx)?;
+        Ok(())
+    }
+
+    /// Counters of the calls made through this node , kept across reconnects.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Requests sent and not answered yet , a steady climb means the server does not keep up.
+    pub fn pending_requests(&self) -> usize {
+        match self.requests.read() {
+            Ok(requests) => requests.len(),
+            Err(poisoned) => poisoned.into_inner().len(),
+        }
+    }
+
+    /// Handshake details of the current connection.
+    /// This is a copy , as a reconnect logs in again and replaces them.
+    pub fn conn_info(&self) -> ConnInfo {
+        match self.info.read() {
+            Ok(info) => info.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Address of the server this node is connected to.
+    pub(crate) fn peer_ip(&self) -> Option<IpAddr> {
+        let stream = self.tcp_stream.lock().ok()?;
+        Some(stream.as_ref()?.peer_addr().ok()?.ip())
+    }
+
     pub fn get_sequence(&self) -> i64 {
-        let lock = self.counter.lock();
-        let seq = lock.unwrap();
-        let i = seq.fetch_add(1, Ordering::Relaxed);
-        return i;
+        next_handle(&lock_recover(&self.counter))
     }
 
-    pub fn list_procedures(&mut self) -> Result<Receiver<VoltTable>, VoltError> {
+    pub fn list_procedures(&self) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
         self.call_sp("@SystemCatalog", volt_param!("PROCEDURES"))
     }
 
-    pub fn call_sp(&mut self, query: &str, param: Vec<&dyn Value>) -> Result<Receiver<VoltTable>, VoltError> {
+    /// Call a procedure and wait for its result , `call_sp` followed by `block_for_result`.
+    /// Use `call_sp` to have several calls in flight at once.
+    pub fn call_sp_sync(&self, query: &str, param: Vec<&dyn Value>) -> Result<VoltTable, VoltError> {
+        let rx = self.call_sp(query, param)?;
+        block_for_result(&rx)
+    }
+
+    /// Like `call_sp_sync`, but sends the call again as `policy` says when it fails for a transient reason.
+    pub fn call_sp_retry(&self, query: &str, param: Vec<&dyn Value>, policy: &RetryPolicy) -> Result<VoltTable, VoltError> {
+        policy.run(query, || self.call_sp_sync(query, param.clone()))
+    }
+
+    /// Call `@Statistics` for `selector` , e.g. `"TABLE"` or `"MEMORY"`.
+    /// With `interval` the counters are the ones since the previous interval call instead of since startup.
+    pub fn statistics(&self, selector: &str, interval: bool) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        let interval = interval as i32;
+        self.call_sp("@Statistics", volt_param!(selector, interval))
+    }
+
+    /// Call `@GetPartitionKeys` for `key_type` , `"INTEGER"` or `"STRING"`, and wait for one key per partition.
+    /// Sending single partition work with these keys spreads it over all partitions.
+    pub fn get_partition_keys(&self, key_type: &str) -> Result<Vec<PartitionKey>, VoltError> {
+        let mut table = self.call_sp_sync("@GetPartitionKeys", volt_param!(key_type))?;
+        PartitionKey::from_table(&mut table)
+    }
+
+    /// Call `@Pause` , putting the cluster in admin mode.
+    /// Only connections with `PortRole::Admin` may still write afterwards , so use one for `resume` too.
+    pub fn pause(&self) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.call_sp("@Pause", volt_param!())
+    }
+
+    /// Call `@Resume` , leaving admin mode. Must go over a `PortRole::Admin` connection like `pause`.
+    pub fn resume(&self) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.call_sp("@Resume", volt_param!())
+    }
+
+    /// Call `@Quiesce` , waiting until exports and DR have drained.
+    pub fn quiesce(&self) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.call_sp("@Quiesce", volt_param!())
+    }
+
+    /// Call `@SnapshotSave` and wait for it , writing a snapshot named `nonce` into the directory `path` on every server.
+    /// With `blocking` the cluster runs no transactions until the snapshot is written.
+    /// The table has one row per server and partition with its `RESULT` , a failure there does not make this an `Err`.
+    pub fn snapshot_save(&self, path: &str, nonce: &str, blocking: bool) -> Result<VoltTable, VoltError> {
+        let blocking = blocking as i32;
+        self.call_sp_sync("@SnapshotSave", volt_param!(path, nonce, blocking))
+    }
+
+    /// Call `@SnapshotRestore` and wait for it , loading the snapshot named `nonce` from the directory `path`.
+    /// The table reports the `RESULT` per table and partition like `snapshot_save`.
+    pub fn snapshot_restore(&self, path: &str, nonce: &str) -> Result<VoltTable, VoltError> {
+        self.call_sp_sync("@SnapshotRestore", volt_param!(path, nonce))
+    }
+
+    /// Send a call , its result comes through the receiver. `call_sp_handle` also returns the handle of the call.
+    pub fn call_sp(&self, query: &str, param: Vec<&dyn Value>) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        let req = self.get_sequence();
+        let (responder, rx) = Responder::channel();
+        self.send_sp(req, query, param, responder)?;
+        Ok(rx)
+    }
+
+    /// Like `call_sp`, but also returns the handle of the call , e.g. to `cancel` it.
+    pub fn call_sp_handle(&self, query: &str, param: Vec<&dyn Value>) -> Result<(i64, Receiver<Result<VoltTable, VoltError>>), VoltError> {
+        let req = self.get_sequence();
+        let (responder, rx) = Responder::channel();
+        self.send_sp(req, query, param, responder)?;
+        Ok((req, rx))
+    }
+
+    /// Stop waiting for the call with `handle` , which then gets `VoltError::Cancelled`.
+    /// The server still executes the call , its response is dropped when it arrives.
+    /// `false` when the call was answered already or the handle is unknown.
+    pub fn cancel(&self, handle: i64) -> Result<bool, VoltError> {
+        let request = self.requests.write()?.remove(&handle);
+        Ok(match request {
+            Some(request) => {
+                request.respond(Err(VoltError::Cancelled));
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Like `call_sp`, but `cb` is run on the listener thread with the response instead of sending it to a channel.
+    /// Keep callbacks short , no other response is handled while one runs.
+    pub fn call_sp_with_callback(&self, query: &str, param: Vec<&dyn Value>, cb: Callback) -> Result<(), VoltError> {
         let req = self.get_sequence();
+        self.send_sp(req, query, param, Responder::Callback(Mutex::new(cb)))
+    }
+
+    /// Same as `call_sp`, but the response must be collected with `block_for_result_timeout` ,
+    /// which gives up after `timeout`.
+    /// A response arriving after the timeout finds no pending request any more and is dropped.
+    pub fn call_sp_timeout(&self, query: &str, param: Vec<&dyn Value>, timeout: Duration) -> Result<TimeoutReceiver, VoltError> {
+        let handle = self.get_sequence();
+        let (responder, receiver) = Responder::channel();
+        self.send_sp(handle, query, param, responder)?;
+        Ok(TimeoutReceiver {
+            handle,
+            timeout,
+            receiver,
+            requests: Arc::clone(&self.requests),
+        })
+    }
+
+    fn send_sp(&self, req: i64, query: &str, param: Vec<&dyn Value>, responder: Responder) -> Result<(), VoltError> {
+        check_params(&param)?;
         let mut proc = new_procedure_invocation(
             req,
             false,
             &param,
             query);
-        let (tx, rx): (Sender<VoltTable>, Receiver<VoltTable>) = mpsc::channel();
-        let shared_sender = Mutex::new(tx);
+        self.send_bytes(req, proc.bytes(), responder, call_span(query, param.len(), req))
+    }
+
+    /// Send a call already encoded with the handle `req` , `cb` gets its response like with `call_sp_with_callback`.
+    /// `procedure` and `params` only describe the call for tracing.
+    /// On failure also says whether `cb` got the failure already , as when the listener failed the pending calls meanwhile.
+    pub(crate) fn send_encoded(&self, req: i64, procedure: &str, params: usize, bs: Vec<u8>, cb: Callback) -> Result<(), (VoltError, bool)> {
+        self.send_request(req, bs, Responder::Callback(Mutex::new(cb)), call_span(procedure, params, req))
+    }
+
+    fn send_bytes(&self, req: i64, bs: Vec<u8>, responder: Responder, span: CallSpan) -> Result<(), VoltError> {
+        self.send_request(req, bs, responder, span).map_err(|(err, _)| err)
+    }
+
+    /// Register and send the call , failing with whether the responder was used already.
+    fn send_request(&self, req: i64, bs: Vec<u8>, responder: Responder, span: CallSpan) -> Result<(), (VoltError, bool)> {
+        if self.closing.load(Ordering::Relaxed) {
+            return Err((VoltError::ConnectionNotAvailable, false));
+        }
         let seq = NetworkRequest {
-            query: true,
             handle: req,
-            num_bytes: proc.slen,
-            sync: true,
-            channel: shared_sender,
+            created: Instant::now(),
+            responder,
+            span,
         };
-        self.requests.write()?.insert(req, seq);
-        let bs = proc.bytes();
-        let tcp_stream = self.tcp_stream.as_mut();
-        match tcp_stream {
-            None => {
-                return Err(VoltError::ConnectionNotAvailable);
+        {
+            // checked under the lock , so the listener failing the pending requests cannot miss this one
+            let mut requests = self.requests.write().map_err(|e| (e.into(), false))?;
+            if self.broken.load(Ordering::Relaxed) {
+                return Err((VoltError::ConnectionNotAvailable, false));
             }
-            Some(stream) => {
-                stream.write_all(&*bs)?;
+            if self.opt.max_outstanding.is_some_and(|max| requests.len() >= max) {
+                return Err((VoltError::BackpressureExceeded, false));
             }
+            requests.insert(req, seq);
+            self.metrics.sent(bs.len(), requests.len());
+        }
+        let sent = match self.tcp_stream.lock() {
+            Err(err) => Err(err.into()),
+            Ok(mut stream) => match stream.as_mut() {
+                None => Err(VoltError::ConnectionNotAvailable),
+                // a TLS session buffers writes , without the flush a call could wait for the next one
+                Some(stream) => stream.write_all(&bs).and_then(|_| stream.flush()).map_err(VoltError::Io),
+            },
+        };
+        if let Err(err) = sent {
+            self.metrics.error();
+            // the caller gets the error , no response is coming for the handle
+            // unless the listener failed it already and the responder has run
+            let removed = match self.requests.write() {
+                Ok(mut requests) => requests.remove(&req).is_some(),
+                Err(poisoned) => poisoned.into_inner().remove(&req).is_some(),
+            };
+            return Err((err, !removed));
         }
-        return Ok(rx);
+        Ok(())
     }
 
-    pub fn upload_jar(&mut self, bs: Vec<u8>) -> Result<Receiver<VoltTable>, VoltError> {
+    /// Call `@UpdateClasses` with the jar `bs` , removing no classes.
+    /// Neither the wire protocol nor `@UpdateClasses` knows compression , the server takes the jar as is ,
+    /// which being a zip file has its classes compressed already.
+    pub fn upload_jar(&self, bs: Vec<u8>) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
         self.call_sp("@UpdateClasses", volt_param!(bs,""))
     }
+
+    /// Call `@UpdateClasses` and wait for it , adding the classes of `jar` and removing the ones matching `delete_pattern`,
+    /// e.g. `"com.example.old.*"`. An empty pattern removes nothing.
+    pub fn deploy_classes(&self, jar: Vec<u8>, delete_pattern: &str) -> Result<VoltTable, VoltError> {
+        self.call_sp_sync("@UpdateClasses", volt_param!(jar, delete_pattern))
+    }
+
+    /// Plan of every statement of `sql` , from `@Explain`.
+    pub fn explain(&self, sql: &str) -> Result<Vec<String>, VoltError> {
+        execution_plans(self.call_sp_sync("@Explain", volt_param!(sql))?)
+    }
+
+    /// Plan of every statement of the procedure `proc` , from `@ExplainProc`.
+    pub fn explain_proc(&self, proc: &str) -> Result<Vec<String>, VoltError> {
+        execution_plans(self.call_sp_sync("@ExplainProc", volt_param!(proc))?)
+    }
+
+    /// Run `statements` as one `@AdHoc` call and wait for it , returning one table per statement.
+    /// The server runs them in one transaction , so one failing rolls back all of them.
+    /// The failure is `VoltError::StatementFailed` with the position of the statement when the server error quotes it.
+    pub fn query_batch(&self, statements: &[&str]) -> Result<Vec<VoltTable>, VoltError> {
+        if statements.is_empty() {
+            return Ok(vec![]);
+        }
+        let sql = statements.iter().map(|s| batch_statement(s)).collect::<Vec<_>>().join(";\n");
+        let tables = match self.call_sp_sync("@AdHoc", volt_param!(sql)) {
+            Ok(table) => table.into_tables(),
+            Err(VoltError::ExecuteFail(info)) => return Err(failed_statement(statements, info)),
+            Err(err) => return Err(err),
+        };
+        match tables.len() == statements.len() {
+            true => Ok(tables),
+            false => Err(VoltError::NoValue(format!("{} tables for {} statements", tables.len(), statements.len()))),
+        }
+    }
+
     /// Use `@AdHoc` proc to query .
-    pub fn query(&mut self, sql: &str) -> Result<Receiver<VoltTable>, VoltError> {
-        let mut zero_vec: Vec<&dyn Value> = Vec::new();
-        zero_vec.push(&sql);
-        return Ok(self.call_sp("@AdHoc", zero_vec)?);
+    pub fn query(&self, sql: &str) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        self.query_with_params(sql, Vec::new())
+    }
+
+    /// Use `@AdHoc` proc to query , binding `params` to the `?` placeholders of `sql` in order.
+    pub fn query_with_params(&self, sql: &str, params: Vec<&dyn Value>) -> Result<Receiver<Result<VoltTable, VoltError>>, VoltError> {
+        let mut all: Vec<&dyn Value> = Vec::with_capacity(params.len() + 1);
+        all.push(&sql);
+        all.extend(params);
+        self.call_sp("@AdHoc", all)
+    }
+
+    /// Send a `@Ping` and wait for the answer , so an `Ok` means the server is alive.
+    pub fn ping(&self) -> Result<(), VoltError> {
+        block_for_result(&self.call_sp("@Ping", volt_param!())?)?;
+        Ok(())
+    }
+
+    /// Send a `@Ping` without waiting , the answer is dropped. This is what the keepalive sends.
+    pub fn ping_async(&self) -> Result<(), VoltError> {
+        Node::send_ping(&self.tcp_stream)
+    }
+
+    fn send_ping(tcp_stream: &Arc<Mutex<Option<Box<dyn Stream>>>>) -> Result<(), VoltError> {
+        Node::send_unanswered(tcp_stream, "@Ping", volt_param!())
     }
 
-    pub fn ping(&mut self) -> Result<(), VoltError> {
-        let zero_vec: Vec<&dyn Value> = Vec::new();
-        let mut proc = new_procedure_invocation(PING_HANDLE, false, &zero_vec, "@Ping");
+    /// Send `@Subscribe` for topology updates when `opt.on_topology_change` wants them.
+    fn subscribe(opt: &NodeOpt, tcp_stream: &Arc<Mutex<Option<Box<dyn Stream>>>>) -> Result<(), VoltError> {
+        match opt.on_topology_change {
+            None => Ok(()),
+            Some(_) => Node::send_unanswered(tcp_stream, "@Subscribe", volt_param!("TOPOLOGY")),
+        }
+    }
+
+    /// Send a call with `PING_HANDLE` , so its answer is dropped.
+    fn send_unanswered(tcp_stream: &Arc<Mutex<Option<Box<dyn Stream>>>>, procedure: &str, params: Vec<&dyn Value>) -> Result<(), VoltError> {
+        let mut proc = new_procedure_invocation(PING_HANDLE, false, &params, procedure);
         let bs = proc.bytes();
-        let res = self.tcp_stream.as_mut();
-        match res {
+        let mut res = tcp_stream.lock()?;
+        match res.as_mut() {
             None => {
                 return Err(VoltError::ConnectionNotAvailable);
             }
             Some(stream) => {
                 stream.write_all(&*bs)?;
+                // a TLS session buffers writes , without this a call could wait for the next one
+                stream.flush()?;
             }
         }
         Ok({})
     }
 
 
-    fn job(mut tcp: &TcpStream, requests: &Arc<RwLock<HashMap<i64, NetworkRequest>>>) -> Result<(), VoltError> {
-        let read_res = tcp.read_u32::<BigEndian>();
-        match read_res {
-            Ok(read) => {
-                if read > 0 {
-                    let mut all = vec![0; read as usize];
-                    tcp.read_exact(&mut all)?;
-                    let mut res = ByteBuffer::from_bytes(&*all);
-                    let _ = res.read_u8()?;
-                    let handle = res.read_i64()?;
-                    if handle == PING_HANDLE {
-                        return Ok({});
-                    }
-                    if let Some(t) = requests.write()?.remove(&handle) {
-                        let info = VoltResponseInfo::new(&mut res, handle)?;
-                        let table = new_volt_table(&mut res, info)?;
-                        let sender = t.channel.lock()?;
-                        sender.send(table).unwrap();
+    fn job(tcp: &mut dyn Stream, frames: &mut FrameReader, requests: &Arc<RwLock<HashMap<i64, NetworkRequest>>>,
+           capture_raw: bool, metrics: &Metrics, on_topology: Option<&TopologyCallback>) -> Result<(), VoltError> {
+        let all = frames.read_frame(tcp)?;
+        metrics.received(all.len() + 4);
+        // the table copies the values out , so the buffer can be reused as soon as it is decoded
+        let mut res = ByteBuffer::from_vec(all);
+        let handled = Node::dispatch(&mut res, requests, capture_raw, metrics, on_topology);
+        frames.recycle(res.into_vec());
+        handled
+    }
+
+    fn dispatch(res: &mut ByteBuffer, requests: &Arc<RwLock<HashMap<i64, NetworkRequest>>>,
+                capture_raw: bool, metrics: &Metrics, on_topology: Option<&TopologyCallback>) -> Result<(), VoltError> {
+        // every message starts with a version and a handle , so the stream can not be trusted after an empty one
+        if res.is_empty() {
+            return Err(VoltError::EmptyResponse);
+        }
+        let _ = res.read_u8()?;
+        let handle = res.read_i64()?;
+        if handle == PING_HANDLE {
+            return Ok({});
+        }
+        if handle == TOPOLOGY_HANDLE {
+            if let Some(on_topology) = on_topology {
+                let update = VoltResponseInfo::new(res, handle).and_then(|info| new_volt_table(res, info));
+                match server_result(update) {
+                    // run on the listener like callbacks of calls , a panic must not take the connection down
+                    Ok(table) => {
+                        if panic::catch_unwind(AssertUnwindSafe(|| on_topology(table))).is_err() {
+                            log_error!("the topology callback panicked");
+                        }
                     }
+                    Err(err) => log_warn!("dropping a topology update: {}", err),
                 }
             }
-            Err(e) => {
-                return Err(VoltError::Io(e));
+            return Ok(());
+        }
+        // not removed in the `if let` , a callback must not run while the lock is held
+        // the whole frame was read already , so skipping the response of an unknown handle keeps the stream aligned
+        let request = requests.write()?.remove(&handle);
+        if let Some(t) = request {
+            let table = VoltResponseInfo::new(res, handle)
+                .and_then(|info| new_volt_table(res, info))
+                .map_err(|err| match capture_raw {
+                    true => VoltError::DecodeError { handle, bytes: hex(res.as_bytes()), err: Box::new(err) },
+                    false => err,
+                });
+            let table = server_result(table);
+            if table.is_err() {
+                metrics.error();
             }
+            t.respond(table);
+        } else {
+            log_debug!("no call {} is waiting , it was cancelled or timed out , dropping its response", handle);
         }
         Ok({})
     }
     pub fn shutdown(&mut self) -> Result<(), VoltError> {
-        let mut stop = self.stop.lock().unwrap();
-        *stop = true;
-        let res = self.tcp_stream.as_mut();
-        match res {
+        *lock_recover(&self.stop) = true;
+        let mut res = self.tcp_stream.lock()?;
+        match res.as_mut() {
             None => {}
             Some(stream) => {
-                stream.shutdown(Shutdown::Both)?;
+                stream.shutdown()?;
             }
         }
-        self.tcp_stream = Box::new(Option::None);
+        *res = Option::None;
         return Ok({});
     }
-    /// Listen on new message come in .
+
+    /// Same as `shutdown` , but consumes the node so its error can be handled instead of only being logged by `Drop`.
+    pub fn close(mut self) -> Result<(), VoltError> {
+        self.shutdown()
+    }
+
+    /// Refuse new requests and wait up to `timeout` for the pending ones to be answered before closing.
+    /// Requests still pending after that fail with `VoltError::ConnectionReset`.
+    /// `shutdown` is the variant that closes right away.
+    pub fn shutdown_graceful(&mut self, timeout: Duration) -> Result<(), VoltError> {
+        self.closing.store(true, Ordering::Relaxed);
+        let start = Instant::now();
+        while !self.requests.read()?.is_empty() && start.elapsed() < timeout {
+            thread::sleep(DRAIN_POLL);
+        }
+        Node::fail_pending(&self.requests)?;
+        self.shutdown()
+    }
+
+    /// Fail the pending requests matching `filter` with the error made by `err`.
+    fn fail_requests<F, E>(requests: &Arc<RwLock<HashMap<i64, NetworkRequest>>>, err: E, filter: F) -> Result<(), VoltError>
+        where F: Fn(&NetworkRequest) -> bool, E: Fn() -> VoltError {
+        let failed: Vec<NetworkRequest> = {
+            let mut requests = requests.write()?;
+            let handles: Vec<i64> = requests.values()
+                .filter(|r| filter(r))
+                .map(|r| r.handle)
+                .collect();
+            handles.iter().filter_map(|handle| requests.remove(handle)).collect()
+        };
+        for t in failed {
+            t.respond(Err(err()));
+        }
+        Ok(())
+    }
+
+    /// Fail the requests which have been waiting longer than `timeout` with `VoltError::Timeout`.
+    fn expire(requests: &Arc<RwLock<HashMap<i64, NetworkRequest>>>, timeout: Duration) -> Result<(), VoltError> {
+        Node::fail_requests(requests, || VoltError::Timeout, |r| r.created.elapsed() >= timeout)
+    }
+
+    /// Fail all pending requests with `VoltError::ConnectionReset`.
+    fn fail_pending(requests: &Arc<RwLock<HashMap<i64, NetworkRequest>>>) -> Result<(), VoltError> {
+        Node::fail_requests(requests, || VoltError::ConnectionReset, |_| true)
+    }
+
+    /// Keep logging in again until it works or the node is stopped, returning the new stream to read from.
+    fn recover(opt: &NodeOpt, tcp_stream: &Arc<Mutex<Option<Box<dyn Stream>>>>, info: &Arc<RwLock<ConnInfo>>,
+               requests: &Arc<RwLock<HashMap<i64, NetworkRequest>>>, stopping: &Arc<Mutex<bool>>) -> Option<Box<dyn Stream>> {
+        if let Ok(mut stream) = tcp_stream.lock() {
+            if let Some(stream) = stream.take() {
+                let _ = stream.shutdown();
+            }
+        }
+        if let Err(err) = Node::fail_pending(requests) {
+            log_error!("failing the pending requests failed: {}", err)
+        }
+        loop {
+            thread::sleep(opt.reconnect_interval);
+            if *lock_recover(stopping) {
+                return None;
+            }
+            let res = Node::connect(opt).and_then(|(stream, conn_info)| {
+                let reader = stream.try_clone()?;
+                *tcp_stream.lock()? = Some(stream);
+                *info.write()? = conn_info;
+                Node::subscribe(opt, tcp_stream)?;
+                Ok(reader)
+            });
+            match res {
+                Ok(reader) => return Some(reader),
+                Err(err) => log_warn!("reconnecting to {}:{} failed: {}", opt.ip_port.ip_host, opt.ip_port.port, err),
+            }
+        }
+    }
+
+    /// Ping every `keepalive_interval` until the node is stopped.
+    fn keepalive(&self) {
+        let interval = match self.opt.keepalive_interval {
+            None => return,
+            Some(interval) => interval,
+        };
+        let tcp_stream = Arc::clone(&self.tcp_stream);
+        let stopping = Arc::clone(&self.stop);
+        thread::spawn(move || {
+            let mut last = Instant::now();
+            loop {
+                thread::sleep(interval.min(KEEPALIVE_POLL));
+                if *lock_recover(&stopping) {
+                    break;
+                }
+                if last.elapsed() >= interval {
+                    // a broken connection is the listener's business
+                    let _ = Node::send_ping(&tcp_stream);
+                    last = Instant::now();
+                }
+            }
+        });
+    }
+
+    /// Listen on new message come in , on a thread named `volt-listener-{host}:{port}-{host id}`.
     fn listen(&mut self) -> Result<(), VoltError>
     {
         let requests = Arc::clone(&self.requests);
 
-        let res = self.tcp_stream.as_mut();
-        return match res {
+        let read_timeout = self.opt.read_timeout;
+        let capture_raw = self.opt.capture_raw;
+        let max_reused_buffer = self.opt.max_reused_buffer;
+        let max_response_size = self.opt.max_response_size;
+        let on_topology = self.opt.on_topology_change.clone();
+        let metrics = Arc::clone(&self.metrics);
+        let reconnect = match self.opt.auto_reconnect {
+            true => Some((self.opt.clone(), Arc::clone(&self.tcp_stream), Arc::clone(&self.info))),
+            false => None,
+        };
+        let res = self.tcp_stream.lock()?;
+        return match res.as_ref() {
             None => {
                 Ok(())
             }
             Some(res) => {
-                let tcp = res.try_clone()?;
+                // The clone shares the socket, including the read/write timeouts set in `Node::new`.
+                let mut tcp = res.try_clone()?;
                 let stopping = Arc::clone(&self.stop);
-                thread::spawn(move || {
+                let broken = Arc::clone(&self.broken);
+                let shared = Arc::clone(&self.tcp_stream);
+                let name = format!("volt-listener-{}:{}-{}", self.opt.ip_port.ip_host, self.opt.ip_port.port, self.info.read()?.host_id);
+                thread::Builder::new().name(name).spawn(move || {
+                    let mut frames = FrameReader::new(max_reused_buffer, max_response_size);
                     loop {
-                        if *stopping.lock().unwrap() {
+                        if *lock_recover(&stopping) {
                             break;
                         } else {
-                            let res = crate::node::Node::job(&tcp, &requests);
+                            let res = crate::node::Node::job(tcp.as_mut(), &mut frames, &requests, capture_raw, &metrics, on_topology.as_ref());
                             match res {
                                 Ok(_) => {}
+                                Err(VoltError::Timeout) => {
+                                    if let Some(timeout) = read_timeout {
+                                        if let Err(err) = crate::node::Node::expire(&requests, timeout) {
+                                            log_error!("expiring requests failed: {}", err)
+                                        }
+                                    }
+                                }
                                 Err(err) => {
-                                    if !*stopping.lock().unwrap() {
-                                        eprintln!("{} ", err)
+                                    if !*lock_recover(&stopping) {
+                                        log_warn!("connection lost: {}", err);
+                                        match &reconnect {
+                                            Some((opt, tcp_stream, info)) => {
+                                                match crate::node::Node::recover(opt, tcp_stream, info, &requests, &stopping) {
+                                                    Some(reader) => {
+                                                        tcp = reader;
+                                                        frames = FrameReader::new(max_reused_buffer, max_response_size);
+                                                    }
+                                                    None => break,
+                                                }
+                                            }
+                                            None => {
+                                                broken.store(true, Ordering::Relaxed);
+                                                // the server may still be sending , e.g. after a response too large to read
+                                                if let Some(stream) = lock_recover(&shared).take() {
+                                                    if let Err(err) = stream.shutdown() {
+                                                        log_debug!("closing the lost connection failed: {}", err)
+                                                    }
+                                                }
+                                                if let Err(err) = crate::node::Node::fail_pending(&requests) {
+                                                    log_error!("failing the pending requests failed: {}", err)
+                                                }
+                                                break;
+                                            }
+                                        }
+                                    } else {
+                                        break;
                                     }
                                 }
                             }
                         }
                     }
-                });
+                })?;
                 Ok(())
             }
         };
@@ -335,33 +1183,1188 @@ impl Node {
 pub struct ConnInfo {
     host_id: i32,
     connection: i64,
+    cluster_start: i64,
     leader_addr: Ipv4Addr,
     build: String,
 }
 
-/// Wait for response, convert response error from volt error to `VoltError`.
-pub fn block_for_result(res: &Receiver<VoltTable>) -> Result<VoltTable, VoltError> {
-    let mut table = res.recv()?;
-    let err = table.has_error();
-    return match err {
-        None => { Ok(table) }
-        Some(err) => { Err(err) }
+impl ConnInfo {
+    /// Id of the host this connection is logged in to.
+    pub fn host_id(&self) -> i32 {
+        self.host_id
+    }
+
+    /// Id the server assigned to this connection.
+    pub fn connection_id(&self) -> i64 {
+        self.connection
+    }
+
+    /// Start time of the cluster in milliseconds since the epoch and the address of its leader ,
+    /// which together tell a restarted cluster from the one connected to before.
+    pub fn cluster_instance_id(&self) -> (i64, Ipv4Addr) {
+        (self.cluster_start, self.leader_addr)
+    }
+
+    /// Address of the cluster leader as reported in the handshake.
+    pub fn leader_addr(&self) -> Ipv4Addr {
+        self.leader_addr
+    }
+
+    /// Build string of the server , e.g. `9.2.1 voltdb-9.2.1-0-g...`.
+    pub fn build_string(&self) -> &str {
+        &self.build
+    }
+
+    /// `(major, minor, patch)` of the server , the first version number in the build string.
+    /// A missing patch level is 0.
+    pub fn version(&self) -> Option<(u32, u32, u32)> {
+        self.build.split(|c: char| !c.is_ascii_digit() && c != '.')
+            .find_map(|token| {
+                let mut parts = token.split('.').map(|p| p.parse::<u32>().ok());
+                let major = parts.next()??;
+                let minor = parts.next()??;
+                let patch = parts.next().unwrap_or(Some(0))?;
+                Some((major, minor, patch))
+            })
+    }
+}
+
+/// A statement of `query_batch` without surrounding whitespace and its own `;`.
+fn batch_statement(statement: &str) -> &str {
+    statement.trim().trim_end_matches(';').trim_end()
+}
+
+/// Point out the statement of a failed `query_batch` whose text the error message contains.
+fn failed_statement(statements: &[&str], info: VoltResponseInfo) -> VoltError {
+    let position = statements.iter().position(|s| info.status_string().contains(batch_statement(s)));
+    match position {
+        Some(statement) => VoltError::StatementFailed { statement, err: Box::new(VoltError::ExecuteFail(info)) },
+        None => VoltError::ExecuteFail(info),
+    }
+}
+
+/// The `EXECUTION_PLAN` column of every row of every table , in order.
+fn execution_plans(table: VoltTable) -> Result<Vec<String>, VoltError> {
+    let mut plans = Vec::new();
+    for mut table in table.into_tables() {
+        while table.advance_row() {
+            let plan = table.get_string_by_column("EXECUTION_PLAN")?;
+            plans.push(plan.ok_or_else(|| VoltError::UnexpectedNull("EXECUTION_PLAN".to_owned()))?);
+        }
+    }
+    Ok(plans)
+}
+
+/// Length of a response from its prefix , `VoltError::ResponseTooLarge` when it is above `max`.
+pub(crate) fn response_size(prefix: u32, max: usize) -> Result<usize, VoltError> {
+    let size = prefix as usize;
+    match size > max {
+        true => Err(VoltError::ResponseTooLarge(size, max)),
+        false => Ok(size),
+    }
+}
+
+/// Fail with `VoltError::UnsupportedServerVersion` when the server is older than `opt.min_version`.
+pub(crate) fn check_version(opt: &NodeOpt, info: &ConnInfo) -> Result<(), VoltError> {
+    let min = match opt.min_version {
+        None => return Ok(()),
+        Some(min) => min,
     };
+    match info.version() {
+        Some(version) if version >= min => Ok(()),
+        _ => Err(VoltError::UnsupportedServerVersion(info.build.clone(), format!("{}.{}.{}", min.0, min.1, min.2))),
+    }
+}
+
+/// Login message sent right after connecting , including the length prefix.
+pub(crate) fn login_message(opt: &NodeOpt) -> Vec<u8> {
+    let mut buffer = ByteBuffer::new();
+    buffer.write_u32(0);
+    // protocol version
+    buffer.write_u8(1);
+    let hash_scheme = match opt.pass_hash {
+        Some(_) => HashScheme::Sha256,
+        None => opt.hash_scheme,
+    };
+    buffer.write_u8(hash_scheme.service_byte());
+    buffer.write_string("database");
+    match &opt.user {
+        None => {
+            buffer.write_string("");
+        }
+        Some(user) => {
+            buffer.write_string(user.as_str());
+        }
+    }
+    match &opt.pass_hash {
+        Some(digest) => buffer.write_bytes(digest),
+        None => {
+            let password = opt.pass.as_deref().unwrap_or("");
+            buffer.write_bytes(&opt.hash_scheme.digest(password.as_bytes()));
+        }
+    }
+
+    buffer.set_wpos(0);
+    buffer.write_u32((buffer.len() - 4) as u32);
+    buffer.into_vec()
+}
+
+/// Parse the login response , without the length prefix.
+pub(crate) fn parse_login_response(bytes: &[u8]) -> Result<ConnInfo, VoltError> {
+    let mut res = ByteBuffer::from_bytes(bytes);
+    let _version = res.read_u8()?;
+    let auth = res.read_u8()?;
+    if auth != 0 {
+        return Err(VoltError::AuthFailed);
+    }
+    let host_id = res.read_i32()?;
+    let connection = res.read_i64()?;
+    let cluster_start = res.read_i64()?;
+    let leader = res.read_i32()?;
+    let bs = (leader as u32).to_be_bytes();
+    let leader_addr = Ipv4Addr::from(bs);
+    // TODO check IP
+    let length = res.read_i32()?;
+    let mut build = vec![0; length as usize];
+    res.read_exact(&mut build)?;
+    let b = from_utf8(&build)?;
+    Ok(ConnInfo {
+        host_id,
+        connection,
+        cluster_start,
+        leader_addr,
+        build: String::from(b),
+    })
+}
+
+/// Pending response of `Node::call_sp_timeout`.
+pub struct TimeoutReceiver {
+    handle: i64,
+    timeout: Duration,
+    receiver: Receiver<Result<VoltTable, VoltError>>,
+    requests: Arc<RwLock<HashMap<i64, NetworkRequest>>>,
+}
+
+impl TimeoutReceiver {
+    /// Handle of the call , e.g. to log it or to `cancel` it.
+    pub fn handle(&self) -> i64 {
+        self.handle
+    }
+}
+
+/// Wait for response , a failure reported by the server comes back as `VoltError::ExecuteFail`.
+pub fn block_for_result(res: &Receiver<Result<VoltTable, VoltError>>) -> Result<VoltTable, VoltError> {
+    res.recv()?
+}
+
+/// Like `block_for_result`, but returns `VoltError::Timeout` when no response came in time.
+/// The request is forgotten on timeout , so a late response will not leak.
+pub fn block_for_result_timeout(res: &TimeoutReceiver) -> Result<VoltTable, VoltError> {
+    match res.receiver.recv_timeout(res.timeout) {
+        Ok(res) => res,
+        Err(RecvTimeoutError::Timeout) => {
+            res.requests.write()?.remove(&res.handle);
+            return Err(VoltError::Timeout);
+        }
+        Err(RecvTimeoutError::Disconnected) => Err(VoltError::RecvError(mpsc::RecvError)),
+    }
 }
 
 pub fn reset() {}
 
 
-/// Create new connection to server .
+/// Split `host:port`, failing with `VoltError::InvalidAddress` when either part is missing or the port is no number.
+fn parse_addr(addr: &str) -> Result<IpPort, VoltError> {
+    let invalid = || VoltError::InvalidAddress(addr.to_string());
+    let (host, port) = addr.rsplit_once(':').ok_or_else(invalid)?;
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    let port = u16::from_str(port).map_err(|_| invalid())?;
+    Ok(IpPort::new(host.to_string(), port))
+}
+
+/// Create new connection to server , using `DEFAULT_CONNECT_TIMEOUT`.
 pub fn get_node(addr: &str) -> Result<Node, VoltError> {
-    let url = addr.split(":").collect::<Vec<&str>>();
-    let host = url.get(0).unwrap().to_string();
-    let port = u16::from_str(url.get(1).unwrap()).unwrap();
-    let ip_port = IpPort::new(host, port);
+    get_node_with_timeout(addr, DEFAULT_CONNECT_TIMEOUT)
+}
+
+/// Guard of `mutex` even if a thread panicked while holding it , the flags and counters behind these locks stay valid.
+fn lock_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Connect to the first of `addrs` which accepts , each one waiting up to `timeout`.
+/// With a single address its error is returned as is , otherwise `VoltError::ConnectFailed` with all of them.
+fn connect_any(addrs: &[SocketAddr], timeout: Option<Duration>) -> Result<TcpStream, VoltError> {
+    let mut errors = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        let socket = match timeout {
+            None => TcpStream::connect(addr),
+            Some(timeout) => TcpStream::connect_timeout(addr, timeout),
+        };
+        match socket {
+            Ok(socket) => return Ok(socket),
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => errors.push(VoltError::ConnectTimeout),
+            Err(e) => errors.push(VoltError::Io(e)),
+        }
+    }
+    if errors.len() == 1 {
+        return Err(errors.remove(0));
+    }
+    Err(VoltError::ConnectFailed(errors))
+}
+
+/// Create new connection to server , failing with `VoltError::ConnectTimeout` after `timeout`.
+pub fn get_node_with_timeout(addr: &str, timeout: Duration) -> Result<Node, VoltError> {
+    let ip_port = parse_addr(addr)?;
     let opt = NodeOpt {
-        ip_port,
-        user: None,
-        pass: None,
+        connect_timeout: Some(timeout),
+        ..NodeOpt::new(ip_port)
     };
     return Node::new(opt);
 }
+
+
+#[cfg(test)]
+mod tests {
+    use crate::encode::{INT_COLUMN, LONG_COLUMN, STRING_COLUMN, VAR_BIN_COLUMN};
+    use crate::hashinator::{Hashinator, partition_leaders};
+    use crate::mock_server::{answer_all, drain, error_response, frame, int_response, tables_response, read_call, read_call_with_params, serve, topology_tables, try_read_call};
+
+    use super::*;
+
+    fn get_i32(table: Result<VoltTable, VoltError>) -> i32 {
+        let mut table = table.unwrap();
+        assert!(table.advance_row());
+        table.get_i32_by_idx(0).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_call_sp() {
+        let ip_port = serve(|mut stream| {
+            let (name, handle) = read_call(&mut stream);
+            assert_eq!(name, "@AdHoc");
+            stream.write_all(&int_response(handle, 42)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(node.conn_info().build_string(), "test");
+        assert_eq!(node.conn_info().cluster_instance_id(), (1_600_000_000_000, Ipv4Addr::LOCALHOST));
+        let rx = node.query("select a from t").unwrap();
+        assert_eq!(get_i32(block_for_result(&rx)), 42);
+    }
+
+    #[test]
+    fn test_server_failure_is_err() {
+        let ip_port = serve(|mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            stream.write_all(&error_response(handle, -2, "constraint violation")).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let rx = node.query("insert into t values (1)").unwrap();
+        match rx.recv().unwrap() {
+            Err(VoltError::ExecuteFail(info)) => assert_eq!(info.status_string(), "constraint violation"),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_read_timeout_expires_request() {
+        let ip_port = serve(|mut stream| {
+            read_call(&mut stream);
+            drain(&mut stream);
+        });
+        let opt = NodeOpt {
+            read_timeout: Some(Duration::from_millis(50)),
+            ..NodeOpt::new(ip_port)
+        };
+        let node = Node::new(opt).unwrap();
+        let rx = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&rx), Err(VoltError::Timeout)));
+        assert!(node.requests.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_timeout_mid_frame() {
+        let ip_port = serve(|mut stream| {
+            let (_, first) = read_call(&mut stream);
+            let response = int_response(first, 1);
+            stream.write_all(&response[..10]).unwrap();
+            // the client gives up on the first call , then sends the second one
+            let (_, second) = read_call(&mut stream);
+            stream.write_all(&response[10..]).unwrap();
+            stream.write_all(&int_response(second, 2)).unwrap();
+            drain(&mut stream);
+        });
+        let opt = NodeOpt {
+            read_timeout: Some(Duration::from_millis(50)),
+            ..NodeOpt::new(ip_port)
+        };
+        let node = Node::new(opt).unwrap();
+        let rx = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&rx), Err(VoltError::Timeout)));
+        let rx = node.query("select a from t").unwrap();
+        assert_eq!(get_i32(block_for_result(&rx)), 2);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let ip_port = serve(|mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            let (_, abandoned) = read_call(&mut stream);
+            let (_, other) = read_call(&mut stream);
+            stream.write_all(&int_response(abandoned, 0)).unwrap();
+            // the cancelled call is answered after all
+            stream.write_all(&int_response(handle, 1)).unwrap();
+            stream.write_all(&int_response(other, 2)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let (handle, rx) = node.call_sp_handle("@AdHoc", volt_param!("select a from t")).unwrap();
+        assert!(node.cancel(handle).unwrap());
+        assert!(!node.cancel(handle).unwrap());
+        assert!(matches!(block_for_result(&rx), Err(VoltError::Cancelled)));
+        // nobody waiting for the cancellation either
+        let (handle, rx) = node.call_sp_handle("@AdHoc", volt_param!("select a from t")).unwrap();
+        drop(rx);
+        assert!(node.cancel(handle).unwrap());
+        let (_, other) = node.call_sp_handle("@AdHoc", volt_param!("select a from t")).unwrap();
+        assert_eq!(get_i32(block_for_result(&other)), 2);
+        assert_eq!(node.pending_requests(), 0);
+    }
+
+    #[test]
+    fn test_call_sp_timeout() {
+        let ip_port = serve(|mut stream| {
+            read_call(&mut stream);
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let rx = node.call_sp_timeout("@AdHoc", volt_param!("select a from t"), Duration::from_millis(50)).unwrap();
+        assert!(node.requests.read().unwrap().contains_key(&rx.handle()));
+        assert!(matches!(block_for_result_timeout(&rx), Err(VoltError::Timeout)));
+        assert!(node.requests.read().unwrap().is_empty());
+    }
+
+    /// First connection never answers and is kept open, or closed after the first call when `close` is set.
+    /// Later connections answer every call with 5.
+    fn serve_flaky(close: bool) -> IpPort {
+        let connections = AtomicI64::new(0);
+        serve(move |mut stream| {
+            if connections.fetch_add(1, Ordering::Relaxed) == 0 {
+                read_call(&mut stream);
+                if !close {
+                    drain(&mut stream);
+                }
+                return;
+            }
+            answer_all(&mut stream, 5);
+        })
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let mut node = Node::new(NodeOpt::new(serve_flaky(false))).unwrap();
+        let pending = node.query("select a from t").unwrap();
+        node.reconnect().unwrap();
+        assert!(matches!(block_for_result(&pending), Err(VoltError::ConnectionReset)));
+        let rx = node.query("select a from t").unwrap();
+        assert_eq!(get_i32(block_for_result(&rx)), 5);
+    }
+
+    #[test]
+    fn test_from_stream() {
+        let ip_port = serve(|mut stream| answer_all(&mut stream, 3));
+        let stream = TcpStream::connect((ip_port.ip_host.as_str(), ip_port.port)).unwrap();
+        let node = Node::from_stream(stream, NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(node.conn_info().build_string(), "test");
+        assert_eq!(get_i32(node.call_sp_sync("@Ping", volt_param!())), 3);
+    }
+
+    #[test]
+    fn test_reconnect_keeps_handles() {
+        let first_handle = Arc::new(AtomicI64::new(0));
+        let seen = Arc::clone(&first_handle);
+        let ip_port = serve(move |mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            if seen.compare_exchange(0, handle, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+                // left unanswered until the client reconnects
+                drain(&mut stream);
+                return;
+            }
+            let old = seen.load(Ordering::SeqCst);
+            assert!(handle > old);
+            // a late answer to the old handle must not reach the new call
+            stream.write_all(&int_response(old, 1)).unwrap();
+            stream.write_all(&int_response(handle, 2)).unwrap();
+            drain(&mut stream);
+        });
+        let mut node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let pending = node.query("select a from t").unwrap();
+        while first_handle.load(Ordering::SeqCst) == 0 {
+            thread::sleep(Duration::from_millis(5));
+        }
+        node.reconnect().unwrap();
+        assert!(matches!(block_for_result(&pending), Err(VoltError::ConnectionReset)));
+        let rx = node.query("select a from t").unwrap();
+        assert_eq!(get_i32(block_for_result(&rx)), 2);
+    }
+
+    #[test]
+    fn test_auto_reconnect() {
+        let opt = NodeOpt {
+            auto_reconnect: true,
+            reconnect_interval: Duration::from_millis(20),
+            ..NodeOpt::new(serve_flaky(true))
+        };
+        let node = Node::new(opt).unwrap();
+        let pending = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&pending), Err(VoltError::ConnectionReset)));
+        for _ in 0..100 {
+            if let Ok(rx) = node.query("select a from t") {
+                if let Ok(table) = block_for_result(&rx) {
+                    assert_eq!(get_i32(Ok(table)), 5);
+                    return;
+                }
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        panic!("node did not reconnect");
+    }
+
+    #[test]
+    fn test_broken_connection() {
+        let ip_port = serve(|mut stream| {
+            read_call(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let pending = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&pending), Err(VoltError::ConnectionReset)));
+        assert!(node.is_broken());
+        assert!(matches!(node.query("select a from t"), Err(VoltError::ConnectionNotAvailable)));
+    }
+
+    #[test]
+    fn test_bad_frame_fails_pending() {
+        let ip_port = serve(|mut stream| {
+            read_call(&mut stream);
+            read_call(&mut stream);
+            // too short to hold a handle
+            stream.write_all(&[0, 0, 0, 2, 0, 1]).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let first = node.query("select a from t").unwrap();
+        let second = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&first), Err(VoltError::ConnectionReset)));
+        assert!(matches!(block_for_result(&second), Err(VoltError::ConnectionReset)));
+        assert!(node.requests.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_health_check() {
+        let ip_port = serve(|mut stream| {
+            let (name, handle) = read_call(&mut stream);
+            assert_eq!(name, "@Ping");
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            // the second ping is never answered
+            read_call(&mut stream);
+            drain(&mut stream);
+        });
+        let mut node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert!(node.is_connected());
+        node.health_check(Duration::from_secs(5)).unwrap();
+        assert!(matches!(node.health_check(Duration::from_millis(50)), Err(VoltError::Timeout)));
+        node.shutdown().unwrap();
+        assert!(!node.is_connected());
+    }
+
+    #[test]
+    fn test_topology_change() {
+        let ip_port = serve(|mut stream| {
+            let (name, handle, params) = read_call_with_params(&mut stream);
+            assert_eq!((name.as_str(), handle), ("@Subscribe", PING_HANDLE));
+            assert!(String::from_utf8_lossy(params.as_bytes()).contains("TOPOLOGY"));
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            stream.write_all(&tables_response(TOPOLOGY_HANDLE, &topology_tables())).unwrap();
+            answer_all(&mut stream, 3);
+        });
+        let (tx, updates) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let opt = NodeOpt {
+            on_topology_change: Some(Arc::new(move |table| tx.lock().unwrap().send(table).unwrap())),
+            ..NodeOpt::new(ip_port)
+        };
+        let node = Node::new(opt).unwrap();
+        let mut update = updates.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(partition_leaders(&mut update).unwrap(), HashMap::from([(0, 1)]));
+        assert!(Hashinator::from_topology(update.extra_table(0).unwrap()).is_ok());
+        // the push is not taken for the answer to a call
+        assert_eq!(get_i32(node.call_sp_sync("@AdHoc", volt_param!("select a from t"))), 3);
+        assert_eq!(node.pending_requests(), 0);
+    }
+
+    #[test]
+    fn test_abandoned_requests() {
+        let ip_port = serve(|mut stream| {
+            let (_, dropped) = read_call(&mut stream);
+            let (_, panicking) = read_call(&mut stream);
+            // answered only once the receiver is gone
+            let (_, last) = read_call(&mut stream);
+            stream.write_all(&int_response(dropped, 1)).unwrap();
+            stream.write_all(&int_response(panicking, 2)).unwrap();
+            stream.write_all(&int_response(last, 3)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        drop(node.query("select a from t").unwrap());
+        node.call_sp_with_callback("@AdHoc", volt_param!("select a from t"), Box::new(|_| panic!("callback failed"))).unwrap();
+        let rx = node.query("select a from t").unwrap();
+        assert_eq!(get_i32(block_for_result(&rx)), 3);
+        assert!(node.is_connected());
+        assert_eq!(node.pending_requests(), 0);
+    }
+
+    #[test]
+    fn test_topology_callback_panics() {
+        let ip_port = serve(|mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            let (_, handle) = read_call(&mut stream);
+            stream.write_all(&tables_response(TOPOLOGY_HANDLE, &topology_tables())).unwrap();
+            stream.write_all(&int_response(handle, 4)).unwrap();
+            answer_all(&mut stream, 5);
+        });
+        let opt = NodeOpt {
+            on_topology_change: Some(Arc::new(|_| panic!("topology callback failed"))),
+            ..NodeOpt::new(ip_port)
+        };
+        let node = Node::new(opt).unwrap();
+        // answered after the update , so the listener survived it
+        assert_eq!(get_i32(node.call_sp_sync("@AdHoc", volt_param!("select a from t"))), 4);
+        assert_eq!(get_i32(node.call_sp_sync("@AdHoc", volt_param!("select a from t"))), 5);
+        assert!(node.is_connected());
+    }
+
+    #[test]
+    fn test_listener_thread_name() {
+        let ip_port = serve(|mut stream| answer_all(&mut stream, 1));
+        let port = ip_port.port;
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let (tx, name) = mpsc::channel();
+        node.call_sp_with_callback("@Ping", volt_param!(), Box::new(move |_| {
+            tx.send(thread::current().name().map(|n| n.to_owned())).unwrap();
+        })).unwrap();
+        // host 1 as the handshake says
+        assert_eq!(name.recv().unwrap(), Some(format!("volt-listener-127.0.0.1:{}-1", port)));
+    }
+
+    #[test]
+    fn test_ping() {
+        let ip_port = serve(|mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            assert_ne!(handle, PING_HANDLE);
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            let (name, handle) = read_call(&mut stream);
+            assert_eq!((name.as_str(), handle), ("@Ping", PING_HANDLE));
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        node.ping().unwrap();
+        node.ping_async().unwrap();
+        assert!(node.requests.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let ip_port = serve(|mut stream| answer_all(&mut stream, 6));
+        let node = Arc::new(Node::new(NodeOpt::new(ip_port)).unwrap());
+        let threads: Vec<_> = (0..4).map(|_| {
+            let node = Arc::clone(&node);
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    assert_eq!(get_i32(node.call_sp_sync("@AdHoc", volt_param!("select a from t"))), 6);
+                }
+            })
+        }).collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert!(node.requests.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_version() {
+        let info = |build: &str| ConnInfo { host_id: 0, connection: 0, cluster_start: 0, leader_addr: Ipv4Addr::LOCALHOST, build: build.to_owned() };
+        assert_eq!(info("9.2.1 voltdb-9.2.1-0-gc3ba2ba").version(), Some((9, 2, 1)));
+        assert_eq!(info("voltdb-10.1-0-g1234").version(), Some((10, 1, 0)));
+        assert_eq!(info("test").version(), None);
+        let opt = NodeOpt { min_version: Some((9, 3, 0)), ..NodeOpt::new(IpPort::new("localhost".to_owned(), 21212)) };
+        assert!(check_version(&opt, &info("10.1 voltdb-10.1")).is_ok());
+        assert!(matches!(check_version(&opt, &info("9.2.1 voltdb-9.2.1")), Err(VoltError::UnsupportedServerVersion(..))));
+        assert!(matches!(check_version(&opt, &info("test")), Err(VoltError::UnsupportedServerVersion(..))));
+        // the mock server calls itself `test`
+        let ip_port = serve(|mut stream| drain(&mut stream));
+        assert!(matches!(Node::new(NodeOpt { ip_port, ..opt }), Err(VoltError::UnsupportedServerVersion(..))));
+    }
+
+    #[test]
+    fn test_keepalive() {
+        let pings = Arc::new(AtomicI64::new(0));
+        let counted = Arc::clone(&pings);
+        let ip_port = serve(move |mut stream| {
+            while let Some((name, _)) = try_read_call(&mut stream) {
+                if name == "@Ping" {
+                    counted.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        let opt = NodeOpt {
+            keepalive_interval: Some(Duration::from_millis(20)),
+            ..NodeOpt::new(ip_port)
+        };
+        let mut node = Node::new(opt).unwrap();
+        let start = Instant::now();
+        while pings.load(Ordering::Relaxed) < 2 && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(pings.load(Ordering::Relaxed) >= 2);
+        node.shutdown().unwrap();
+        // the keepalive thread lets go of the stream once it noticed the stop
+        thread::sleep(KEEPALIVE_POLL * 3);
+        assert_eq!(Arc::strong_count(&node.tcp_stream), 1);
+    }
+
+    #[test]
+    fn test_shutdown_graceful() {
+        let ip_port = serve(|mut stream| {
+            let (_, answered) = read_call(&mut stream);
+            read_call(&mut stream);
+            thread::sleep(Duration::from_millis(30));
+            stream.write_all(&int_response(answered, 3)).unwrap();
+            drain(&mut stream);
+        });
+        let mut node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let answered = node.query("select a from t").unwrap();
+        let dropped = node.query("select a from t").unwrap();
+        node.shutdown_graceful(Duration::from_millis(200)).unwrap();
+        assert_eq!(get_i32(block_for_result(&answered)), 3);
+        assert!(matches!(block_for_result(&dropped), Err(VoltError::ConnectionReset)));
+        assert!(matches!(node.query("select a from t"), Err(VoltError::ConnectionNotAvailable)));
+    }
+
+    #[test]
+    fn test_connect_any() {
+        let closed = || {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let open = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = connect_any(&[closed(), open.local_addr().unwrap()], Some(Duration::from_secs(1))).unwrap();
+        assert_eq!(socket.peer_addr().unwrap(), open.local_addr().unwrap());
+        assert!(matches!(connect_any(&[closed()], None), Err(VoltError::Io(_))));
+        match connect_any(&[closed(), closed()], None) {
+            Err(VoltError::ConnectFailed(errors)) => assert_eq!(errors.len(), 2),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_capture_raw() {
+        let ip_port = serve(|mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            // status and nothing else
+            let mut body = ByteBuffer::new();
+            body.write_u8(0);
+            body.write_i64(handle);
+            body.write_u8(0);
+            stream.write_all(&frame(body)).unwrap();
+            answer_all(&mut stream, 4);
+        });
+        let opt = NodeOpt { capture_raw: true, ..NodeOpt::new(ip_port.clone()) };
+        let node = Node::new(opt).unwrap();
+        match block_for_result(&node.query("select a from t").unwrap()) {
+            Err(VoltError::DecodeError { handle, bytes, .. }) => assert_eq!(bytes, format!("00{:016x}00", handle)),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        // the next response is read from where the broken one ended
+        assert_eq!(get_i32(block_for_result(&node.query("select a from t").unwrap())), 4);
+        drop(node);
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert!(matches!(block_for_result(&node.query("select a from t").unwrap()), Err(VoltError::Io(_))));
+    }
+
+    #[test]
+    fn test_orphan_handle() {
+        let ip_port = serve(|mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            // the answer to a call that timed out long ago
+            let orphan = VoltTable::new_table(vec![STRING_COLUMN], vec!["A".to_owned()]);
+            stream.write_all(&tables_response(handle + 1000, &[orphan])).unwrap();
+            stream.write_all(&int_response(handle, 6)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(get_i32(block_for_result(&node.query("select a from t").unwrap())), 6);
+    }
+
+    #[test]
+    fn test_login_timeout() {
+        // accepts , but never answers the login
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let ip_port = IpPort::new("127.0.0.1".to_owned(), listener.local_addr().unwrap().port());
+        let opt = NodeOpt { login_timeout: Some(Duration::from_millis(50)), ..NodeOpt::new(ip_port) };
+        let start = Instant::now();
+        assert!(matches!(Node::new(opt), Err(VoltError::LoginTimeout)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_pending_requests() {
+        let ip_port = serve(|mut stream| {
+            let (_, first) = read_call(&mut stream);
+            read_call(&mut stream);
+            stream.write_all(&int_response(first, 1)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(node.pending_requests(), 0);
+        let first = node.query("select a from t").unwrap();
+        let _second = node.query("select a from t").unwrap();
+        block_for_result(&first).unwrap();
+        assert_eq!(node.pending_requests(), 1);
+        let debug = format!("{:?}", node);
+        assert!(debug.contains("pending_requests: 1"), "{}", debug);
+        assert!(debug.contains("build: \"test\""), "{}", debug);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let ip_port = serve(|mut stream| {
+            let (name, handle, mut params) = read_call_with_params(&mut stream);
+            assert_eq!(name, "@SnapshotSave");
+            assert_eq!(params.read_i16().unwrap(), 3);
+            assert_eq!(params.read_i8().unwrap(), STRING_COLUMN);
+            assert_eq!(params.read_string().unwrap(), "/tmp/backup");
+            assert_eq!(params.read_i8().unwrap(), STRING_COLUMN);
+            assert_eq!(params.read_string().unwrap(), "nightly");
+            assert_eq!(params.read_i8().unwrap(), INT_COLUMN);
+            assert_eq!(params.read_i32().unwrap(), 1);
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            let (name, handle, mut params) = read_call_with_params(&mut stream);
+            assert_eq!(name, "@SnapshotRestore");
+            assert_eq!(params.read_i16().unwrap(), 2);
+            stream.write_all(&int_response(handle, 0)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        node.snapshot_save("/tmp/backup", "nightly", true).unwrap();
+        node.snapshot_restore("/tmp/backup", "nightly").unwrap();
+    }
+
+    #[test]
+    fn test_send_failure_removes_request() {
+        let ip_port = serve(|mut stream| drain(&mut stream));
+        let mut node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        node.shutdown().unwrap();
+        // registered , then the stream turns out to be gone
+        assert!(matches!(node.query("select a from t"), Err(VoltError::ConnectionNotAvailable)));
+        assert_eq!(node.pending_requests(), 0);
+    }
+
+    #[test]
+    fn test_poisoned_lock() {
+        let ip_port = serve(|mut stream| answer_all(&mut stream, 5));
+        let mut node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        thread::scope(|s| {
+            let _ = s.spawn(|| {
+                let _counter = node.counter.lock().unwrap();
+                let _stop = node.stop.lock().unwrap();
+                panic!("worker died");
+            }).join();
+        });
+        assert!(node.counter.is_poisoned());
+        assert_eq!(get_i32(block_for_result(&node.query("select a from t").unwrap())), 5);
+        node.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_metrics() {
+        let ip_port = serve(|mut stream| {
+            let (_, first) = read_call(&mut stream);
+            let (_, second) = read_call(&mut stream);
+            stream.write_all(&int_response(first, 1)).unwrap();
+            stream.write_all(&error_response(second, -2, "constraint")).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let first = node.query("select a from t").unwrap();
+        let second = node.query("select a from t").unwrap();
+        block_for_result(&first).unwrap();
+        assert!(block_for_result(&second).is_err());
+        let metrics = node.metrics();
+        assert_eq!(metrics.requests, 2);
+        assert_eq!(metrics.errors, 1);
+        assert_eq!(metrics.in_flight_peak, 2);
+        assert!(metrics.bytes_sent > 0);
+        // the length of a response does not depend on its handle
+        assert_eq!(metrics.bytes_received as usize, int_response(0, 1).len() + error_response(0, -2, "constraint").len());
+    }
+
+    #[test]
+    fn test_close() {
+        let (tx, rx) = mpsc::channel();
+        let ip_port = serve(move |mut stream| {
+            drain(&mut stream);
+            tx.send(()).unwrap();
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        node.close().unwrap();
+        // the server sees the connection go away
+        rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    }
+
+    #[test]
+    fn test_call_sp_with_callback() {
+        let ip_port = serve(|mut stream| {
+            let (_, handle) = read_call(&mut stream);
+            stream.write_all(&int_response(handle, 8)).unwrap();
+            // the second call is never answered
+            read_call(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..2 {
+            let tx = tx.clone();
+            node.call_sp_with_callback("@AdHoc", volt_param!("select a from t"), Box::new(move |res| {
+                tx.send(res).unwrap();
+            })).unwrap();
+        }
+        assert_eq!(get_i32(rx.recv().unwrap()), 8);
+        assert!(matches!(rx.recv().unwrap(), Err(VoltError::ConnectionReset)));
+    }
+
+    #[test]
+    fn test_max_outstanding() {
+        // the first call is answered only once the third one was refused
+        let (refused, wait) = mpsc::channel();
+        let wait = Mutex::new(wait);
+        let ip_port = serve(move |mut stream| {
+            let (_, first) = read_call(&mut stream);
+            read_call(&mut stream);
+            wait.lock().unwrap().recv().unwrap();
+            stream.write_all(&int_response(first, 1)).unwrap();
+            answer_all(&mut stream, 2);
+        });
+        let opt = NodeOpt {
+            max_outstanding: Some(2),
+            ..NodeOpt::new(ip_port)
+        };
+        let node = Node::new(opt).unwrap();
+        let first = node.query("select a from t").unwrap();
+        let _second = node.query("select a from t").unwrap();
+        assert!(matches!(node.query("select a from t"), Err(VoltError::BackpressureExceeded)));
+        refused.send(()).unwrap();
+        assert_eq!(get_i32(block_for_result(&first)), 1);
+        let third = node.query("select a from t").unwrap();
+        assert_eq!(get_i32(block_for_result(&third)), 2);
+    }
+
+    #[test]
+    fn test_query_with_params() {
+        let ip_port = serve(|mut stream| {
+            let (name, handle, mut params) = read_call_with_params(&mut stream);
+            assert_eq!(name, "@AdHoc");
+            assert_eq!(params.read_i16().unwrap(), 3);
+            assert_eq!(params.read_i8().unwrap(), STRING_COLUMN);
+            assert_eq!(params.read_string().unwrap(), "select a from t where b = ? and c = ?");
+            assert_eq!(params.read_i8().unwrap(), INT_COLUMN);
+            assert_eq!(params.read_i32().unwrap(), 4);
+            stream.write_all(&int_response(handle, 1)).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let rx = node.query_with_params("select a from t where b = ? and c = ?", volt_param!(4, "x")).unwrap();
+        assert_eq!(get_i32(block_for_result(&rx)), 1);
+    }
+
+    #[test]
+    fn test_deploy_classes() {
+        let ip_port = serve(|mut stream| {
+            let (name, handle, mut params) = read_call_with_params(&mut stream);
+            assert_eq!(name, "@UpdateClasses");
+            assert_eq!(params.read_i16().unwrap(), 2);
+            assert_eq!(params.read_i8().unwrap(), VAR_BIN_COLUMN);
+            assert_eq!(params.read_i32().unwrap(), 2);
+            assert_eq!(params.read_bytes(2).unwrap(), vec![1, 2]);
+            assert_eq!(params.read_i8().unwrap(), STRING_COLUMN);
+            assert_eq!(params.read_string().unwrap(), "com.example.old.*");
+            stream.write_all(&error_response(handle, -2, "class not found")).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert!(matches!(node.deploy_classes(vec![1, 2], "com.example.old.*"), Err(VoltError::ExecuteFail(_))));
+    }
+
+    #[test]
+    fn test_explain() {
+        let plan_table = |plans: &[&str]| {
+            let mut table = VoltTable::new_table(vec![STRING_COLUMN], vec!["EXECUTION_PLAN".to_owned()]);
+            for plan in plans.iter().copied() {
+                table.add_row(volt_param!(plan)).unwrap();
+            }
+            table
+        };
+        let ip_port = serve(move |mut stream| {
+            let (name, handle, mut params) = read_call_with_params(&mut stream);
+            assert_eq!(name, "@Explain");
+            assert_eq!(params.read_i16().unwrap(), 1);
+            let tables = [plan_table(&["scan a"]), plan_table(&["scan b"])];
+            stream.write_all(&tables_response(handle, &tables)).unwrap();
+            let (name, handle) = read_call(&mut stream);
+            assert_eq!(name, "@ExplainProc");
+            stream.write_all(&tables_response(handle, &[plan_table(&["insert", "index scan"])])).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(node.explain("select * from a; select * from b;").unwrap(), vec!["scan a", "scan b"]);
+        assert_eq!(node.explain_proc("Insert").unwrap(), vec!["insert", "index scan"]);
+    }
+
+    #[test]
+    fn test_query_batch() {
+        let count_table = |count: i64| {
+            let mut table = VoltTable::new_table(vec![LONG_COLUMN], vec!["modified_tuples".to_owned()]);
+            table.add_row(volt_param!(count)).unwrap();
+            table
+        };
+        let ip_port = serve(move |mut stream| {
+            let (name, handle, mut params) = read_call_with_params(&mut stream);
+            assert_eq!(name, "@AdHoc");
+            assert_eq!(params.read_i16().unwrap(), 1);
+            assert_eq!(params.read_i8().unwrap(), STRING_COLUMN);
+            assert_eq!(params.read_string().unwrap(), "insert into a values (1);\ninsert into a values (2)");
+            stream.write_all(&tables_response(handle, &[count_table(1), count_table(1)])).unwrap();
+            let (_, handle) = read_call(&mut stream);
+            stream.write_all(&error_response(handle, -2, "Failed to plan for statement (insert into nope values (1)) object not found")).unwrap();
+            let (_, handle) = read_call(&mut stream);
+            stream.write_all(&error_response(handle, -2, "constraint violation")).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let tables = node.query_batch(&["insert into a values (1);", " insert into a values (2)"]).unwrap();
+        assert_eq!(tables.len(), 2);
+        let statements = ["insert into a values (1)", "insert into nope values (1)"];
+        assert!(matches!(node.query_batch(&statements), Err(VoltError::StatementFailed { statement: 1, .. })));
+        assert!(matches!(node.query_batch(&statements), Err(VoltError::ExecuteFail(_))));
+        assert!(node.query_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_statistics() {
+        let ip_port = serve(|mut stream| {
+            for interval in [1, 0] {
+                let (name, handle, mut params) = read_call_with_params(&mut stream);
+                assert_eq!(name, "@Statistics");
+                assert_eq!(params.read_i16().unwrap(), 2);
+                assert_eq!(params.read_i8().unwrap(), STRING_COLUMN);
+                assert_eq!(params.read_string().unwrap(), "MEMORY");
+                assert_eq!(params.read_i8().unwrap(), INT_COLUMN);
+                assert_eq!(params.read_i32().unwrap(), interval);
+                stream.write_all(&int_response(handle, interval)).unwrap();
+            }
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(get_i32(block_for_result(&node.statistics("MEMORY", true).unwrap())), 1);
+        assert_eq!(get_i32(block_for_result(&node.statistics("MEMORY", false).unwrap())), 0);
+    }
+
+    #[test]
+    fn test_admin_procedures() {
+        let ip_port = serve(|mut stream| {
+            for (i, expected) in ["@Pause", "@Resume", "@Quiesce"].iter().enumerate() {
+                let (name, handle, mut params) = read_call_with_params(&mut stream);
+                assert_eq!(&name, expected);
+                assert_eq!(params.read_i16().unwrap(), 0);
+                stream.write_all(&int_response(handle, i as i32)).unwrap();
+            }
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(get_i32(block_for_result(&node.pause().unwrap())), 0);
+        assert_eq!(get_i32(block_for_result(&node.resume().unwrap())), 1);
+        assert_eq!(get_i32(block_for_result(&node.quiesce().unwrap())), 2);
+    }
+
+    #[test]
+    fn test_call_sp_sync() {
+        let ip_port = serve(|mut stream| {
+            let (name, handle) = read_call(&mut stream);
+            assert_eq!(name, "Insert");
+            stream.write_all(&int_response(handle, 5)).unwrap();
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        assert_eq!(get_i32(node.call_sp_sync("Insert", volt_param!(1))), 5);
+        // the server hung up after the first call
+        assert!(node.call_sp_sync("Insert", volt_param!(1)).is_err());
+    }
+
+    #[test]
+    fn test_next_handle_skips_ping() {
+        assert_eq!(PING_HANDLE, i64::MIN);
+        let counter = AtomicI64::new(i64::MAX);
+        assert_eq!(next_handle(&counter), i64::MAX);
+        assert_eq!(next_handle(&counter), i64::MIN + 1);
+        assert_eq!(next_handle(&counter), i64::MIN + 2);
+    }
+
+    #[test]
+    fn test_frame_reader() {
+        let mut bytes = int_response(7, 1);
+        bytes.extend(0u32.to_be_bytes());
+        let mut frames = FrameReader::new(DEFAULT_MAX_REUSED_BUFFER, DEFAULT_MAX_RESPONSE_SIZE);
+        let mut input = &bytes[..];
+        assert_eq!(frames.read_frame(&mut input).unwrap().len(), bytes.len() - 8);
+        assert!(frames.read_frame(&mut input).unwrap().is_empty());
+        assert!(matches!(frames.read_frame(&mut input), Err(VoltError::Io(_))));
+    }
+
+    #[test]
+    fn test_pathological_lengths() {
+        // an empty message breaks the connection
+        let ip_port = serve(|mut stream| {
+            read_call(&mut stream);
+            stream.write_all(&0u32.to_be_bytes()).unwrap();
+            drain(&mut stream);
+        });
+        let node = Node::new(NodeOpt::new(ip_port)).unwrap();
+        let rx = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&rx), Err(VoltError::ConnectionReset)));
+        assert!(node.is_broken());
+
+        // a message shorter than announced is waited for no longer than the read timeout
+        let ip_port = serve(|mut stream| {
+            read_call(&mut stream);
+            stream.write_all(&100u32.to_be_bytes()).unwrap();
+            stream.write_all(&[0; 10]).unwrap();
+            drain(&mut stream);
+        });
+        let opt = NodeOpt { read_timeout: Some(Duration::from_millis(50)), ..NodeOpt::new(ip_port) };
+        let node = Node::new(opt).unwrap();
+        let rx = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&rx), Err(VoltError::Timeout)));
+    }
+
+    #[test]
+    fn test_max_response_size() {
+        let bytes = int_response(7, 1);
+        let mut frames = FrameReader::new(DEFAULT_MAX_REUSED_BUFFER, 8);
+        let mut input = &bytes[..];
+        match frames.read_frame(&mut input) {
+            Err(VoltError::ResponseTooLarge(size, 8)) => assert_eq!(size, bytes.len() - 4),
+            other => panic!("unexpected {:?}", other),
+        }
+        let mut frames = FrameReader::new(DEFAULT_MAX_REUSED_BUFFER, bytes.len() - 4);
+        let mut input = &bytes[..];
+        assert!(frames.read_frame(&mut input).is_ok());
+    }
+
+    #[test]
+    fn test_response_too_large_closes() {
+        let (tx, rx) = mpsc::channel();
+        let ip_port = serve(move |mut stream| {
+            read_call(&mut stream);
+            stream.write_all(&(1u32 << 20).to_be_bytes()).unwrap();
+            stream.write_all(&[0; 64]).unwrap();
+            // returns once the client closed its end
+            drain(&mut stream);
+            tx.send(()).unwrap();
+        });
+        let node = Node::new(NodeOpt { max_response_size: 1024, ..NodeOpt::new(ip_port) }).unwrap();
+        let rx_call = node.query("select a from t").unwrap();
+        assert!(matches!(block_for_result(&rx_call), Err(VoltError::ConnectionReset)));
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(node.is_broken());
+        assert!(!node.is_connected());
+    }
+
+    #[test]
+    fn test_frame_buffer_reuse() {
+        let bytes = [int_response(1, 1), int_response(2, 2), int_response(3, 3)].concat();
+        let mut input = &bytes[..];
+        let mut frames = FrameReader::new(DEFAULT_MAX_REUSED_BUFFER, DEFAULT_MAX_RESPONSE_SIZE);
+        let first = frames.read_frame(&mut input).unwrap();
+        let ptr = first.as_ptr();
+        frames.recycle(first);
+        let second = frames.read_frame(&mut input).unwrap();
+        assert_eq!(second.as_ptr(), ptr);
+        assert_eq!(ByteBuffer::from_vec(second).read_bytes(9).unwrap()[1..], 2i64.to_be_bytes());
+        // a buffer above the limit is not kept
+        let mut frames = FrameReader::new(0, DEFAULT_MAX_RESPONSE_SIZE);
+        let first = frames.read_frame(&mut input).unwrap();
+        frames.recycle(first);
+        assert_eq!(frames.frame.capacity(), 0);
+    }
+
+    #[test]
+    fn test_parse_addr() {
+        assert_eq!(parse_addr("localhost:21212").unwrap(), IpPort::new("localhost".to_string(), 21212));
+        for addr in ["localhost", ":21212", "localhost:", "localhost:port", "localhost:70000"] {
+            match parse_addr(addr) {
+                Err(VoltError::InvalidAddress(s)) => assert_eq!(s, addr),
+                other => panic!("{} parsed as {:?}", addr, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_opts_builder() {
+        let opts = Opts::new(vec![IpPort::new("a".to_string(), 21212)])
+            .add_host(IpPort::new("b".to_string(), 21213))
+            .with_user("user".to_string())
+            .with_password("pass".to_string());
+        assert_eq!(opts.0.ip_ports.len(), 2);
+        let opt = opts.to_node_opt(1).unwrap();
+        assert_eq!(opt.ip_port, IpPort::new("b".to_string(), 21213));
+        assert_eq!(opt.user.as_deref(), Some("user"));
+        assert_eq!(opt.pass.as_deref(), Some("pass"));
+        assert!(opts.to_node_opt(2).is_none());
+    }
+
+    #[test]
+    fn test_hash_scheme() {
+        assert_eq!(HashScheme::default(), HashScheme::Sha256);
+        assert_eq!(HashScheme::Sha1.service_byte(), 0);
+        assert_eq!(HashScheme::Sha256.service_byte(), 1);
+        assert_eq!(HashScheme::Sha1.digest(b"").len(), 20);
+        assert_eq!(HashScheme::Sha256.digest(b"").len(), 32);
+        assert_eq!(HashScheme::Sha1.digest(b"abc")[..4], [0xa9, 0x99, 0x3e, 0x36]);
+    }
+
+    #[test]
+    fn test_pass_hash() {
+        let ip_port = IpPort::new("localhost".to_owned(), 21212);
+        let plain = NodeOpt { pass: Some("secret".to_owned()), ..NodeOpt::new(ip_port.clone()) };
+        let mut digest = [0; 32];
+        digest.copy_from_slice(&Sha256::digest(b"secret"));
+        let hashed = NodeOpt {
+            pass: Some("ignored".to_owned()),
+            hash_scheme: HashScheme::Sha1,
+            pass_hash: Some(digest),
+            ..NodeOpt::new(ip_port)
+        };
+        assert_eq!(login_message(&plain), login_message(&hashed));
+    }
+}
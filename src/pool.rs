@@ -1,20 +1,27 @@
 use std::{
     fmt,
     sync::{
-        Arc,
+        Arc, Mutex, RwLock, RwLockReadGuard,
         atomic::{AtomicUsize, Ordering},
     },
 };
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::{block_for_result, Node, node, NodeOpt, Opts, Value, VoltError, VoltTable};
 
 #[derive(Debug)]
 struct InnerPool {
     opts: Opts,
-    pool: Vec<Node>,
+    pool: Vec<RwLock<Node>>,
+    // checkouts of every connection in `pool`
+    users: Vec<AtomicUsize>,
+    // when every connection in `pool` was last given back , or opened
+    returned: Vec<Mutex<Instant>>,
 }
 
+/// How long `Pool::with_validate_on_checkout` waits for the answer to its `@Ping`.
+pub const VALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
 
 impl InnerPool {
     pub fn node_sizes(&self) -> usize {
@@ -22,19 +29,14 @@ impl InnerPool {
     }
 
     fn to_node_opt(&self, i: usize) -> NodeOpt {
-        return NodeOpt {
-            ip_port: self.opts.0.ip_ports.get(i).cloned().unwrap(),
-            pass: self.opts.0.pass.clone(),
-            user: self.opts.0.user.clone(),
-        };
-    }
-    fn get_node(&mut self, idx: usize) -> &mut Node {
-        return self.pool.get_mut(idx).unwrap();
+        return self.opts.to_node_opt(i).unwrap();
     }
     fn new(size: usize, opts: Opts) -> Result<InnerPool, VoltError> {
         let mut pool = InnerPool {
             opts,
             pool: Vec::with_capacity(size),
+            users: (0..size).map(|_| AtomicUsize::new(0)).collect(),
+            returned: (0..size).map(|_| Mutex::new(Instant::now())).collect(),
         };
         let total = pool.node_sizes();
         for i in 0..size {
@@ -46,7 +48,7 @@ impl InnerPool {
     fn new_conn(&mut self, idx: usize) -> Result<(), VoltError> {
         match node::Node::new(self.to_node_opt(idx)) {
             Ok(conn) => {
-                self.pool.push(conn);
+                self.pool.push(RwLock::new(conn));
                 Ok(())
             }
             Err(err) => Err(err),
@@ -59,29 +61,84 @@ pub struct Pool {
     size: usize,
     total: Arc<AtomicUsize>,
     inner_pool: InnerPool,
+    validate_on_checkout: bool,
+    max_idle: Option<Duration>,
+}
+
+/// Connections of a `Pool` checked out right now and not.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PoolStats {
+    pub size: usize,
+    pub in_use: usize,
+    pub idle: usize,
 }
 
 impl fmt::Debug for Pool {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = self.stats();
         write!(
             f,
-            "Pool total: {}, size: {}",
+            "Pool total: {}, size: {}, in use: {}",
             self.total.load(Ordering::Relaxed),
-            self.size
+            self.size,
+            stats.in_use
         )
     }
 }
 
 impl Pool {
-    fn _get_conn(&mut self) -> Result<PooledConn, VoltError> {
+    fn _get_conn(&self) -> Result<PooledConn<'_>, VoltError> {
         let total = self.total.fetch_add(1, Ordering::Relaxed);
-        let idx = total % self.size;
+        let start = total % self.size;
+        let users = &self.inner_pool.users;
+        // the first idle one in round-robin order , or the next one shared when all are in use
+        let idle = (0..self.size)
+            .map(|i| (start + i) % self.size)
+            .find(|idx| users[*idx].compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed).is_ok());
+        let idx = idle.unwrap_or_else(|| {
+            users[start].fetch_add(1, Ordering::AcqRel);
+            start
+        });
+        // a shared one is in use , so it is not checked
+        if idle.is_some() {
+            if let Err(err) = self.validate(idx) {
+                users[idx].fetch_sub(1, Ordering::AcqRel);
+                return Err(err);
+            }
+        }
+        let conn = match self.inner_pool.pool[idx].read() {
+            Ok(conn) => conn,
+            Err(err) => {
+                users[idx].fetch_sub(1, Ordering::AcqRel);
+                return Err(err.into());
+            }
+        };
         Ok(PooledConn {
             created: SystemTime::now(),
-            conn: self.inner_pool.get_node(idx),
+            conn,
+            users: &users[idx],
+            returned: &self.inner_pool.returned[idx],
         })
     }
 
+    /// Reconnect the idle connection `idx` when it was idle longer than `max_idle` ,
+    /// or does not answer a `@Ping` with `validate_on_checkout`.
+    fn validate(&self, idx: usize) -> Result<(), VoltError> {
+        if !self.validate_on_checkout && self.max_idle.is_none() {
+            return Ok(());
+        }
+        let idle = match self.inner_pool.returned[idx].lock() {
+            Ok(returned) => returned.elapsed(),
+            Err(poisoned) => poisoned.into_inner().elapsed(),
+        };
+        let mut node = self.inner_pool.pool[idx].write()?;
+        let stale = self.max_idle.is_some_and(|max| idle >= max);
+        if stale || (self.validate_on_checkout && node.health_check(VALIDATION_TIMEOUT).is_err()) {
+            node.reconnect()?;
+        }
+        Ok(())
+    }
+
     pub fn new<T: Into<Opts>>(opts: T) -> Result<Pool, VoltError> {
         Pool::new_manual(10, opts)
     }
@@ -92,23 +149,59 @@ impl Pool {
             inner_pool: pool,
             size,
             total: Arc::new(AtomicUsize::from(0 as usize)),
-
+            validate_on_checkout: false,
+            max_idle: None,
         })
     }
 
-    pub fn get_conn(&mut self) -> Result<PooledConn, VoltError> {
+    /// Ping an idle connection before handing it out , and reconnect it when no answer came within `VALIDATION_TIMEOUT`.
+    /// Costs a round trip per checkout , but the first call on a connection that died while idle does not fail.
+    pub fn with_validate_on_checkout(mut self, validate: bool) -> Pool {
+        self.validate_on_checkout = validate;
+        self
+    }
+
+    /// Reconnect a connection which was not checked out for `max_idle` when it is checked out next ,
+    /// before firewalls or the server drop it. Connections are only checked at checkout , no thread watches them.
+    pub fn with_max_idle(mut self, max_idle: Duration) -> Pool {
+        self.max_idle = Some(max_idle);
+        self
+    }
+
+    pub fn get_conn(&self) -> Result<PooledConn<'_>, VoltError> {
+        self._get_conn()
+    }
+
+    /// Check out a connection , an idle one if there is any. It goes back to the pool when the guard is dropped.
+    /// Nodes can be shared , so when all are in use the next one in round-robin order is handed out again.
+    pub fn checkout(&self) -> Result<PooledConn<'_>, VoltError> {
         self._get_conn()
     }
+
+    pub fn stats(&self) -> PoolStats {
+        let in_use = self.inner_pool.users.iter().filter(|u| u.load(Ordering::Relaxed) > 0).count();
+        PoolStats {
+            size: self.size,
+            in_use,
+            idle: self.size - in_use,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct PooledConn<'a> {
     created: SystemTime,
-    conn: &'a mut Node,
+    conn: RwLockReadGuard<'a, Node>,
+    users: &'a AtomicUsize,
+    returned: &'a Mutex<Instant>,
 }
 
 impl<'a> Drop for PooledConn<'a> {
     fn drop(&mut self) {
+        if let Ok(mut returned) = self.returned.lock() {
+            *returned = Instant::now();
+        }
+        self.users.fetch_sub(1, Ordering::AcqRel);
 //        let since = SystemTime::now().duration_since(self.created);
         // TODO record error ,
         //   println!("used {:?} ", since)
@@ -119,6 +212,9 @@ impl<'a> PooledConn<'a> {
     pub fn query(&mut self, sql: &str) -> Result<VoltTable, VoltError> {
         return block_for_result(&self.conn.query(sql)?);
     }
+    pub fn query_with_params(&mut self, sql: &str, params: Vec<&dyn Value>) -> Result<VoltTable, VoltError> {
+        block_for_result(&self.conn.query_with_params(sql, params)?)
+    }
     pub fn list_procedures(&mut self) -> Result<VoltTable, VoltError> {
         return block_for_result(&self.conn.list_procedures()?);
     }
@@ -129,3 +225,77 @@ impl<'a> PooledConn<'a> {
         return block_for_result(&self.conn.upload_jar(bs)?);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::mock_server::{answer_all, serve};
+
+    use super::*;
+
+    #[test]
+    fn test_checkout() {
+        let ip_port = serve(|mut stream| {
+            thread::spawn(move || answer_all(&mut stream, 4));
+        });
+        let pool = Pool::new_manual(2, Opts::new(vec![ip_port])).unwrap();
+        assert_eq!(pool.stats(), PoolStats { size: 2, in_use: 0, idle: 2 });
+        let mut first = pool.checkout().unwrap();
+        let second = pool.checkout().unwrap();
+        assert!(!std::ptr::eq(&*first.conn, &*second.conn));
+        assert_eq!(pool.stats(), PoolStats { size: 2, in_use: 2, idle: 0 });
+        // all in use , so the first one is shared
+        let third = pool.checkout().unwrap();
+        assert!(std::ptr::eq(&*first.conn, &*third.conn));
+        drop(second);
+        assert_eq!(pool.stats().in_use, 1);
+        drop(third);
+        assert_eq!(pool.stats(), PoolStats { size: 2, in_use: 1, idle: 1 });
+        let mut table = first.query("select a from t").unwrap();
+        assert!(table.advance_row());
+        assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(4));
+        drop(first);
+        assert_eq!(pool.stats().idle, 2);
+    }
+
+    /// First connection is closed right after logging in , later ones answer every call with 6.
+    fn serve_dying(connections: Arc<AtomicUsize>) -> crate::IpPort {
+        serve(move |mut stream| {
+            if connections.fetch_add(1, Ordering::Relaxed) > 0 {
+                thread::spawn(move || answer_all(&mut stream, 6));
+            }
+        })
+    }
+
+    #[test]
+    fn test_validate_on_checkout() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let pool = Pool::new_manual(1, Opts::new(vec![serve_dying(Arc::clone(&connections))])).unwrap()
+            .with_validate_on_checkout(true);
+        while !pool.inner_pool.pool[0].read().unwrap().is_broken() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        let mut table = pool.checkout().unwrap().query("select a from t").unwrap();
+        assert!(table.advance_row());
+        assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(6));
+        assert_eq!(connections.load(Ordering::Relaxed), 2);
+        // answered , so not reconnected
+        pool.checkout().unwrap();
+        assert_eq!(connections.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_max_idle() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let pool = Pool::new_manual(1, Opts::new(vec![serve_dying(Arc::clone(&connections))])).unwrap()
+            .with_max_idle(Duration::ZERO);
+        assert!(pool.checkout().unwrap().query("select a from t").is_ok());
+        assert_eq!(connections.load(Ordering::Relaxed), 2);
+        // shared while in use , so not reconnected under the other user
+        let first = pool.checkout().unwrap();
+        let second = pool.checkout().unwrap();
+        assert_eq!(connections.load(Ordering::Relaxed), 3);
+        drop((first, second));
+    }
+}
@@ -1,16 +1,23 @@
 use bytebuffer::ByteBuffer;
 
-use crate::encode::Value;
+use crate::encode::{Value, VoltError};
 
 #[allow(dead_code)]
 pub(crate) struct ProcedureInvocation<'a> {
     handle: i64,
+    // not sent , the invocation has no read-only flag on the wire.
+    // Whether a procedure is read-only comes from the catalog of the server , which routes it by that alone.
     is_query: bool,
     params: &'a Vec<&'a dyn Value>,
     query: String,
     pub(crate) slen: i32, // length of pi once serialized
 }
 
+/// Fail with the first parameter which can not be sent.
+pub(crate) fn check_params(params: &[&dyn Value]) -> Result<(), VoltError> {
+    params.iter().try_for_each(|p| p.check())
+}
+
 pub(crate) fn new_procedure_invocation<'a>(handle: i64, is_query: bool, params: &'a Vec<&dyn Value>, query: &str) -> ProcedureInvocation<'a> {
     ProcedureInvocation {
         handle,
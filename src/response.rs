@@ -28,6 +28,26 @@ impl Default for ResponseStatus {
     }
 }
 
+impl From<&ResponseStatus> for i8 {
+    fn from(s: &ResponseStatus) -> Self {
+        match s {
+            ResponseStatus::Success => 1,
+            ResponseStatus::UserAbort => -1,
+            ResponseStatus::GracefulFailure => -2,
+            ResponseStatus::UnexpectedFailure => -3,
+            ResponseStatus::ConnectionLost => -4,
+            ResponseStatus::ServerUnavailable => -5,
+            ResponseStatus::ConnectionTimeout => -6,
+            ResponseStatus::ResponseUnknown => -7,
+            ResponseStatus::TXNRestart => -8,
+            ResponseStatus::OperationalFailure => -9,
+            ResponseStatus::UnsupportedDynamicChange => -13,
+            ResponseStatus::UninitializedAppStatusCode => -128,
+            ResponseStatus::Customized(s) => *s,
+        }
+    }
+}
+
 impl From<i8> for ResponseStatus {
     fn from(s: i8) -> Self {
         match s {
@@ -56,20 +76,49 @@ pub struct VoltResponseInfo {
     status: ResponseStatus,
     status_string: String,
     app_status: ResponseStatus,
-    app_status_string: String,
+    app_status_string: Option<String>,
     cluster_round_trip_time: i32,
     num_tables: i16,
 }
 
 
 impl VoltResponseInfo {
-    #[allow(dead_code)]
     pub fn get_rows_number(&self) -> i16 {
         return self.num_tables;
     }
     pub fn get_status(&self) -> ResponseStatus {
         return self.status.clone();
     }
+
+    /// Handle of the call this is the response to.
+    pub fn handle(&self) -> i64 {
+        self.handle
+    }
+
+    /// Status byte of the call , `1` is success and the negative values are the failures of `ResponseStatus`.
+    pub fn status(&self) -> i8 {
+        i8::from(&self.status)
+    }
+
+    /// Message explaining a failed status , empty on success.
+    pub fn status_string(&self) -> &str {
+        &self.status_string
+    }
+
+    /// Status set by the procedure with `setAppStatusCode`, `-128` when it set none.
+    pub fn app_status(&self) -> i8 {
+        i8::from(&self.app_status)
+    }
+
+    /// Message set by the procedure with `setAppStatusString`.
+    pub fn app_status_string(&self) -> Option<&str> {
+        self.app_status_string.as_deref()
+    }
+
+    /// Milliseconds the call spent inside the cluster , as measured by the server that received it.
+    pub fn cluster_round_trip_time(&self) -> i32 {
+        self.cluster_round_trip_time
+    }
 }
 
 impl VoltResponseInfo {
@@ -81,9 +130,9 @@ impl VoltResponseInfo {
             status_string = bytebuffer.read_string()?;
         }
         let app_status = ResponseStatus::from(bytebuffer.read_i8()?);
-        let mut app_status_string = "".to_owned();
+        let mut app_status_string = None;
         if fields_present & (1 << 7) != 0 {
-            app_status_string = bytebuffer.read_string()?;
+            app_status_string = Some(bytebuffer.read_string()?);
         }
         let cluster_round_trip_time = bytebuffer.read_i32()?;
         let num_tables = bytebuffer.read_i16()?;
@@ -101,3 +150,32 @@ impl VoltResponseInfo {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_info() {
+        let mut bs = ByteBuffer::new();
+        bs.write_u8((1 << 5) | (1 << 7));
+        bs.write_i8(-2);
+        bs.write_string("constraint violation");
+        bs.write_i8(7);
+        bs.write_string("app");
+        bs.write_i32(3);
+        bs.write_i16(0);
+        let info = VoltResponseInfo::new(&mut bs, 9).unwrap();
+        assert_eq!(info.status(), -2);
+        assert_eq!(info.get_status(), ResponseStatus::GracefulFailure);
+        assert_eq!(info.status_string(), "constraint violation");
+        assert_eq!(info.app_status(), 7);
+        assert_eq!(info.app_status_string(), Some("app"));
+        assert_eq!(info.cluster_round_trip_time(), 3);
+        let err = crate::encode::VoltError::ExecuteFail(info.clone());
+        assert_eq!(err.to_string(), "Call 9 failed with status -2 (GracefulFailure): constraint violation");
+        for status in [1, -1, -9, -13, -128, 42] {
+            assert_eq!(i8::from(&ResponseStatus::from(status)), status);
+        }
+    }
+}
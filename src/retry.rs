@@ -0,0 +1,139 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::encode::VoltError;
+use crate::response::ResponseStatus;
+use crate::table::VoltTable;
+
+/// When `Node::call_sp_retry` and `Client::call_sp_retry` send a failed call again.
+/// Only procedures marked with `idempotent` are retried , a call failing with `VoltError::ConnectionReset`
+/// may have been executed nonetheless. Others are sent once.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts including the first one.
+    pub max_attempts: u32,
+    /// Pause before the second attempt , doubled for every further one.
+    pub backoff: Duration,
+    /// Upper bound of the pause.
+    pub max_backoff: Duration,
+    /// Which failures are worth another attempt , `is_transient` by default.
+    pub retryable: fn(&VoltError) -> bool,
+    idempotent: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new(3, Duration::from_millis(100))
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, backoff: Duration) -> Self {
+        RetryPolicy {
+            max_attempts,
+            backoff,
+            max_backoff: Duration::from_secs(2),
+            retryable: is_transient,
+            idempotent: vec![],
+        }
+    }
+
+    /// Allow retrying `procedure` , which must be safe to run more than once.
+    /// Marking `@AdHoc` allows it for every query.
+    pub fn idempotent(mut self, procedure: &str) -> Self {
+        self.idempotent.push(procedure.to_owned());
+        self
+    }
+
+    pub fn is_idempotent(&self, procedure: &str) -> bool {
+        self.idempotent.iter().any(|p| p == procedure)
+    }
+
+    fn pause(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// Run `call` until it succeeds , fails for good or runs out of attempts.
+    /// Running out gives `VoltError::RetriesExhausted` with the last failure.
+    pub(crate) fn run<F>(&self, procedure: &str, mut call: F) -> Result<VoltTable, VoltError>
+        where F: FnMut() -> Result<VoltTable, VoltError> {
+        if !self.is_idempotent(procedure) {
+            return call();
+        }
+        let mut attempt = 1;
+        loop {
+            match call() {
+                Err(err) if (self.retryable)(&err) => {
+                    if attempt >= self.max_attempts {
+                        return Err(VoltError::RetriesExhausted(attempt, Box::new(err)));
+                    }
+                    thread::sleep(self.pause(attempt));
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+/// Failures a later attempt may not run into , the connection broke or was busy or the cluster could not run the call right now.
+pub fn is_transient(err: &VoltError) -> bool {
+    match err {
+        VoltError::Io(_) | VoltError::RecvError(_) | VoltError::ConnectionReset | VoltError::ConnectionNotAvailable
+        | VoltError::ConnectTimeout | VoltError::Timeout | VoltError::BackpressureExceeded => true,
+        VoltError::ExecuteFail(info) => matches!(info.get_status(),
+            ResponseStatus::ConnectionLost | ResponseStatus::ServerUnavailable | ResponseStatus::TXNRestart),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn failing(calls: &Cell<u32>, failures: u32, err: fn() -> VoltError) -> impl FnMut() -> Result<VoltTable, VoltError> + '_ {
+        move || {
+            calls.set(calls.get() + 1);
+            if calls.get() <= failures {
+                return Err(err());
+            }
+            Ok(VoltTable::new_table(vec![], vec![]))
+        }
+    }
+
+    #[test]
+    fn test_retry() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(1)).idempotent("Get");
+        let calls = Cell::new(0);
+        assert!(policy.run("Get", failing(&calls, 2, || VoltError::ConnectionReset)).is_ok());
+        assert_eq!(calls.get(), 3);
+
+        let calls = Cell::new(0);
+        match policy.run("Get", failing(&calls, 5, || VoltError::Timeout)) {
+            Err(VoltError::RetriesExhausted(3, err)) => assert!(matches!(*err, VoltError::Timeout)),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        assert_eq!(calls.get(), 3);
+
+        // not idempotent
+        let calls = Cell::new(0);
+        assert!(matches!(policy.run("Put", failing(&calls, 5, || VoltError::ConnectionReset)), Err(VoltError::ConnectionReset)));
+        assert_eq!(calls.get(), 1);
+
+        // not retryable
+        let calls = Cell::new(0);
+        assert!(matches!(policy.run("Get", failing(&calls, 5, || VoltError::AuthFailed)), Err(VoltError::AuthFailed)));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy { max_backoff: Duration::from_millis(300), ..RetryPolicy::new(5, Duration::from_millis(100)) };
+        assert_eq!(policy.pause(1), Duration::from_millis(100));
+        assert_eq!(policy.pause(2), Duration::from_millis(200));
+        assert_eq!(policy.pause(3), Duration::from_millis(300));
+    }
+}
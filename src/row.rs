@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use bigdecimal::BigDecimal;
+use bytebuffer::ByteBuffer;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+
+use crate::column_value::ColumnValue;
+use crate::encode::{*};
+use crate::geography::GeographyPoint;
+use crate::table::{Column, RowBuf, VoltTable};
+
+/// Index of the column named `column` , which must be of type `tp`.
+pub(crate) fn typed_column_index(columns: &[Column], cn_to_ci: &HashMap<String, i16>, column: &str, tp: i8) -> Result<i16, VoltError> {
+    let idx = *cn_to_ci.get(column.to_uppercase().as_str()).ok_or_else(|| VoltError::NoSuchColumn(column.to_owned()))?;
+    let actual = columns.get(idx as usize).ok_or_else(|| VoltError::NoValue(idx.to_string()))?.header_type;
+    if actual != tp {
+        return Err(VoltError::TypeMismatch(column.to_owned(), tp, actual));
+    }
+    Ok(idx)
+}
+
+/// Value of the integer column `column` as `T` , which must be at least as wide as the column.
+/// A narrower column is widened , a wider one or one of another type fails with `VoltError::TypeMismatch` instead of being truncated.
+pub(crate) fn integer_value<T: TryFrom<i64>>(column: &Column, bs: &[u8]) -> Result<Option<T>, VoltError> {
+    let width = std::mem::size_of::<T>();
+    let expected = match width {
+        1 => TINYINT_COLUMN,
+        2 => SHORT_COLUMN,
+        4 => INT_COLUMN,
+        _ => LONG_COLUMN,
+    };
+    let null: &[u8] = match column.header_type {
+        TINYINT_COLUMN if width >= 1 => &NULL_BIT_VALUE,
+        SHORT_COLUMN if width >= 2 => &NULL_SHORT_VALUE,
+        INT_COLUMN if width >= 4 => &NULL_INT_VALUE,
+        LONG_COLUMN if width >= 8 => &NULL_LONG_VALUE,
+        actual => return Err(VoltError::TypeMismatch(column.header_name.clone(), expected, actual)),
+    };
+    if bs.len() != null.len() {
+        return Err(VoltError::NoValue(format!("{} bytes of column {}", null.len(), column.header_name)));
+    }
+    if bs == null {
+        return Ok(None);
+    }
+    // sign extend from the width of the column
+    let value = bs.iter().fold(if (bs[0] as i8) < 0 { -1 } else { 0 }, |acc: i64, b| (acc << 8) | *b as i64);
+    T::try_from(value).map(Some).map_err(|_| VoltError::ValueOutOfRange(format!("{} of column {}", value, column.header_name)))
+}
+
+/// Iterator over the rows of a `VoltTable` , see `VoltTable::rows`.
+pub struct Rows<'a> {
+    pub(crate) columns: &'a [Column],
+    pub(crate) cn_to_ci: &'a HashMap<String, i16>,
+    pub(crate) rows: &'a RowBuf,
+    pub(crate) row_index: &'a mut i32,
+    pub(crate) column_offsets: &'a mut Vec<i32>,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = Result<Row<'a>, VoltError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = *self.row_index + 1;
+        let bytes = self.rows.get(next as usize)?;
+        *self.row_index = next;
+        // the table caches offsets of its current row
+        self.column_offsets.clear();
+        Some(Row::new(self.columns, self.cn_to_ci, bytes))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.rows.len().saturating_sub((*self.row_index + 1) as usize);
+        (left, Some(left))
+    }
+}
+
+/// One row of a `VoltTable`.
+/// The `get_*` getters read a column by index , the `get_*_by_name` ones by name and check its type.
+#[derive(Debug)]
+pub struct Row<'a> {
+    columns: &'a [Column],
+    cn_to_ci: &'a HashMap<String, i16>,
+    bytes: &'a [u8],
+    offsets: Vec<i32>,
+}
+
+impl<'a> Row<'a> {
+    pub(crate) fn new(columns: &'a [Column], cn_to_ci: &'a HashMap<String, i16>, bytes: &'a [u8]) -> Result<Row<'a>, VoltError> {
+        let mut reader = ByteBuffer::from_bytes(bytes);
+        let mut offsets = Vec::with_capacity(columns.len() + 1);
+        let mut offset = 0;
+        offsets.push(offset);
+        for column in columns {
+            offset += VoltTable::col_length(&mut reader, offset, column.header_type)?;
+            offsets.push(offset);
+        }
+        if offset as usize > bytes.len() {
+            return Err(VoltError::NoValue(format!("column past the end of the row at {}", offset)));
+        }
+        Ok(Row { columns, cn_to_ci, bytes, offsets })
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.columns
+    }
+
+    /// Index of the column named `column` , ignoring case.
+    pub fn column_index(&self, column: &str) -> Option<i16> {
+        self.cn_to_ci.get(column.to_uppercase().as_str()).copied()
+    }
+
+    /// Raw bytes of a column as they came over the wire.
+    pub fn get_bytes_by_idx(&self, column: i16) -> Result<&'a [u8], VoltError> {
+        let start = *self.offsets.get(column as usize).ok_or_else(|| VoltError::NoValue(column.to_string()))?;
+        let end = *self.offsets.get(column as usize + 1).ok_or_else(|| VoltError::NoValue(column.to_string()))?;
+        Ok(&self.bytes[start as usize..end as usize])
+    }
+
+    /// Decode a column , use an `Option` for columns which may be NULL.
+    pub fn take<T: Value>(&self, column: i16) -> Result<T, VoltError> {
+        let bs = self.get_bytes_by_idx(column)?;
+        let table_column = self.columns.get(column as usize).ok_or_else(|| VoltError::NoValue(column.to_string()))?;
+        T::from_bytes(bs.to_vec(), table_column)
+    }
+
+    /// Decode a column of whatever type it has.
+    pub fn get_value(&self, column: i16) -> Result<ColumnValue, VoltError> {
+        let table_column = self.columns.get(column as usize).ok_or_else(|| VoltError::NoValue(column.to_string()))?;
+        ColumnValue::decode(table_column, self.get_bytes_by_idx(column)?)
+    }
+
+    /// Every column by its name , NULLs as `ColumnValue::Null`.
+    pub fn to_map(&self) -> Result<HashMap<String, ColumnValue>, VoltError> {
+        let mut map = HashMap::with_capacity(self.columns.len());
+        for (idx, column) in self.columns.iter().enumerate() {
+            map.insert(column.header_name.clone(), ColumnValue::decode(column, self.get_bytes_by_idx(idx as i16)?)?);
+        }
+        Ok(map)
+    }
+
+    /// Like `take` , but finds the column by name.
+    pub fn fetch<T: Value>(&self, column: &str) -> Result<T, VoltError> {
+        let idx = self.column_index(column).ok_or_else(|| VoltError::NoSuchColumn(column.to_owned()))?;
+        self.take(idx)
+    }
+
+    fn take_typed<T: Value>(&self, column: &str, tp: i8) -> Result<T, VoltError> {
+        self.take(typed_column_index(self.columns, self.cn_to_ci, column, tp)?)
+    }
+
+    /// The integer getters widen narrower integer columns , see `VoltTable::get_i64`.
+    pub fn get_i8(&self, column: i16) -> Result<Option<i8>, VoltError> {
+        self.integer(column)
+    }
+
+    pub fn get_i16(&self, column: i16) -> Result<Option<i16>, VoltError> {
+        self.integer(column)
+    }
+
+    pub fn get_i32(&self, column: i16) -> Result<Option<i32>, VoltError> {
+        self.integer(column)
+    }
+
+    pub fn get_i64(&self, column: i16) -> Result<Option<i64>, VoltError> {
+        self.integer(column)
+    }
+
+    fn integer<T: TryFrom<i64>>(&self, column: i16) -> Result<Option<T>, VoltError> {
+        let table_column = self.columns.get(column as usize).ok_or_else(|| VoltError::NoValue(column.to_string()))?;
+        integer_value(table_column, self.get_bytes_by_idx(column)?)
+    }
+
+    pub fn get_f32(&self, column: i16) -> Result<Option<f32>, VoltError> {
+        self.take(column)
+    }
+
+    pub fn get_f64(&self, column: i16) -> Result<Option<f64>, VoltError> {
+        self.take(column)
+    }
+
+    pub fn get_bool(&self, column: i16) -> Result<Option<bool>, VoltError> {
+        self.take(column)
+    }
+
+    pub fn get_string(&self, column: i16) -> Result<Option<String>, VoltError> {
+        self.take(column)
+    }
+
+    pub fn get_decimal(&self, column: i16) -> Result<Option<BigDecimal>, VoltError> {
+        self.take(column)
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn get_time(&self, column: i16) -> Result<Option<DateTime<Utc>>, VoltError> {
+        self.take(column)
+    }
+
+    pub fn get_bytes(&self, column: i16) -> Result<Option<Vec<u8>>, VoltError> {
+        self.take(column)
+    }
+
+    pub fn get_point(&self, column: i16) -> Result<Option<GeographyPoint>, VoltError> {
+        self.take(column)
+    }
+
+    pub fn get_i8_by_name(&self, column: &str) -> Result<Option<i8>, VoltError> {
+        self.take_typed(column, TINYINT_COLUMN)
+    }
+
+    pub fn get_i16_by_name(&self, column: &str) -> Result<Option<i16>, VoltError> {
+        self.take_typed(column, SHORT_COLUMN)
+    }
+
+    pub fn get_i32_by_name(&self, column: &str) -> Result<Option<i32>, VoltError> {
+        self.take_typed(column, INT_COLUMN)
+    }
+
+    pub fn get_i64_by_name(&self, column: &str) -> Result<Option<i64>, VoltError> {
+        self.take_typed(column, LONG_COLUMN)
+    }
+
+    pub fn get_f32_by_name(&self, column: &str) -> Result<Option<f32>, VoltError> {
+        self.take_typed(column, FLOAT_COLUMN)
+    }
+
+    pub fn get_f64_by_name(&self, column: &str) -> Result<Option<f64>, VoltError> {
+        self.take_typed(column, FLOAT_COLUMN)
+    }
+
+    pub fn get_bool_by_name(&self, column: &str) -> Result<Option<bool>, VoltError> {
+        self.take_typed(column, TINYINT_COLUMN)
+    }
+
+    pub fn get_string_by_name(&self, column: &str) -> Result<Option<String>, VoltError> {
+        self.take_typed(column, STRING_COLUMN)
+    }
+
+    pub fn get_decimal_by_name(&self, column: &str) -> Result<Option<BigDecimal>, VoltError> {
+        self.take_typed(column, DECIMAL_COLUMN)
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn get_time_by_name(&self, column: &str) -> Result<Option<DateTime<Utc>>, VoltError> {
+        self.take_typed(column, TIMESTAMP_COLUMN)
+    }
+
+    pub fn get_bytes_by_name(&self, column: &str) -> Result<Option<Vec<u8>>, VoltError> {
+        self.take_typed(column, VAR_BIN_COLUMN)
+    }
+
+    pub fn get_point_by_name(&self, column: &str) -> Result<Option<GeographyPoint>, VoltError> {
+        self.take_typed(column, GEOGRAPHY_POINT_COLUMN)
+    }
+}
@@ -0,0 +1,238 @@
+use std::io::{Read, Result, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+
+use crate::encode::VoltError;
+use crate::node::TlsConfig;
+
+/// Connection to a server , the wire protocol is the same over plain TCP and TLS.
+pub(crate) trait Stream: Read + Write + Send {
+    /// Second handle on the same connection , the listener reads from it while requests are written.
+    fn try_clone(&self) -> Result<Box<dyn Stream>>;
+    fn shutdown(&self) -> Result<()>;
+    fn peer_addr(&self) -> Result<SocketAddr>;
+}
+
+impl Stream for TcpStream {
+    fn try_clone(&self) -> Result<Box<dyn Stream>> {
+        Ok(Box::new(TcpStream::try_clone(self)?))
+    }
+
+    fn shutdown(&self) -> Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        TcpStream::peer_addr(self)
+    }
+}
+
+/// Wrap a freshly connected socket, doing the TLS handshake when `tls` is set.
+pub(crate) fn wrap(socket: TcpStream, host: &str, tls: Option<&TlsConfig>) -> std::result::Result<Box<dyn Stream>, VoltError> {
+    match tls {
+        None => Ok(Box::new(socket)),
+        Some(config) => tls::wrap(socket, host, config),
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+mod tls {
+    use std::net::TcpStream;
+
+    use crate::encode::VoltError;
+    use crate::node::TlsConfig;
+
+    use super::Stream;
+
+    pub(super) fn wrap(_socket: TcpStream, _host: &str, _config: &TlsConfig) -> Result<Box<dyn Stream>, VoltError> {
+        Err(VoltError::Tls("built without the `tls` feature".to_owned()))
+    }
+}
+
+#[cfg(feature = "tls")]
+mod tls {
+    use std::io::{Error, ErrorKind, Read, Result, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+    use rustls::pki_types::{CertificateDer, ServerName};
+    use rustls::pki_types::pem::PemObject;
+
+    use crate::encode::VoltError;
+    use crate::node::TlsConfig;
+
+    use super::Stream;
+
+    /// The listener and the writers share one TLS session.
+    /// A read waits for data on the socket without holding the lock , so writes never wait for a blocked read.
+    struct TlsStream {
+        tls: Arc<Mutex<StreamOwned<ClientConnection, TcpStream>>>,
+        // same socket as in `tls`, to wait on and to close it without the lock
+        socket: TcpStream,
+    }
+
+    fn tls_error<E: ToString>(e: E) -> VoltError {
+        VoltError::Tls(e.to_string())
+    }
+
+    pub(super) fn wrap(mut socket: TcpStream, host: &str, config: &TlsConfig) -> std::result::Result<Box<dyn Stream>, VoltError> {
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(&config.ca_cert_path).map_err(tls_error)? {
+            roots.add(cert.map_err(tls_error)?).map_err(tls_error)?;
+        }
+        let client_config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions().map_err(tls_error)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let name = config.sni_hostname.as_deref().unwrap_or(host);
+        let name = ServerName::try_from(name.to_owned()).map_err(tls_error)?;
+        let mut conn = ClientConnection::new(Arc::new(client_config), name).map_err(tls_error)?;
+        while conn.is_handshaking() {
+            conn.complete_io(&mut socket).map_err(tls_error)?;
+        }
+        let handle = socket.try_clone()?;
+        Ok(Box::new(TlsStream {
+            tls: Arc::new(Mutex::new(StreamOwned::new(conn, socket))),
+            socket: handle,
+        }))
+    }
+
+    impl TlsStream {
+        fn lock(&self) -> Result<MutexGuard<'_, StreamOwned<ClientConnection, TcpStream>>> {
+            self.tls.lock().map_err(|e| Error::other(e.to_string()))
+        }
+    }
+
+    impl Read for TlsStream {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            loop {
+                match self.lock()?.conn.reader().read(buf) {
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                    res => return res,
+                }
+                // nothing decrypted yet , wait for the socket with the user's read timeout
+                self.socket.peek(&mut [0; 1])?;
+                let mut tls = self.lock()?;
+                let StreamOwned { conn, sock } = &mut *tls;
+                if conn.read_tls(sock)? == 0 {
+                    // closed , the reader tells a close_notify from a dropped connection
+                    return conn.reader().read(buf);
+                }
+                conn.process_new_packets().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+                while conn.wants_write() {
+                    conn.write_tls(sock)?;
+                }
+            }
+        }
+    }
+
+    impl Write for TlsStream {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.lock()?.write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            self.lock()?.flush()
+        }
+    }
+
+    impl Stream for TlsStream {
+        fn try_clone(&self) -> Result<Box<dyn Stream>> {
+            Ok(Box::new(TlsStream {
+                tls: Arc::clone(&self.tls),
+                socket: self.socket.try_clone()?,
+            }))
+        }
+
+        fn shutdown(&self) -> Result<()> {
+            // a writer may hold the lock , the close_notify is a courtesy only
+            if let Ok(mut tls) = self.tls.try_lock() {
+                tls.conn.send_close_notify();
+                let _ = tls.flush();
+            }
+            self.socket.shutdown(std::net::Shutdown::Both)
+        }
+
+        fn peer_addr(&self) -> Result<SocketAddr> {
+            self.socket.peer_addr()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{TcpListener, TcpStream};
+    use std::path::PathBuf;
+
+    use crate::encode::VoltError;
+    use crate::node::TlsConfig;
+
+    use super::wrap;
+
+    #[test]
+    fn test_tls_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let config = TlsConfig::new(PathBuf::from("does/not/exist.pem"), None);
+        match wrap(socket, "localhost", Some(&config)) {
+            Err(VoltError::Tls(_)) => {}
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("expected a TLS error"),
+        }
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_handshake() {
+        use std::io::{Read, Write};
+        use std::sync::Arc;
+        use std::thread;
+        use std::time::Duration;
+
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let ca_path = std::env::temp_dir().join(format!("voltdb-client-test-ca-{}.pem", std::process::id()));
+        std::fs::write(&ca_path, cert.cert.pem()).unwrap();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()));
+        let server_config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions().unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![CertificateDer::from(cert.cert.der().to_vec())], key).unwrap();
+        let server_config = Arc::new(server_config);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // echo server
+        thread::spawn(move || {
+            for socket in listener.incoming() {
+                let conn = rustls::ServerConnection::new(Arc::clone(&server_config)).unwrap();
+                let mut tls = rustls::StreamOwned::new(conn, socket.unwrap());
+                let mut buf = [0; 64];
+                while let Ok(n @ 1..) = tls.read(&mut buf) {
+                    tls.write_all(&buf[..n]).unwrap();
+                    tls.flush().unwrap();
+                }
+            }
+        });
+
+        let config = TlsConfig::new(ca_path.clone(), None);
+        let mut stream = wrap(TcpStream::connect(addr).unwrap(), "localhost", Some(&config)).unwrap();
+        // a reader waiting for data must not hold up writes
+        let mut reader = stream.try_clone().unwrap();
+        let echo = thread::spawn(move || {
+            let mut buf = [0; 5];
+            reader.read_exact(&mut buf).unwrap();
+            buf
+        });
+        thread::sleep(Duration::from_millis(50));
+        stream.write_all(b"hello").unwrap();
+        stream.flush().unwrap();
+        assert_eq!(&echo.join().unwrap(), b"hello");
+        stream.shutdown().unwrap();
+
+        let config = TlsConfig::new(ca_path.clone(), Some("other".to_string()));
+        let res = wrap(TcpStream::connect(addr).unwrap(), "localhost", Some(&config));
+        assert!(matches!(res, Err(VoltError::Tls(_))));
+        let _ = std::fs::remove_file(ca_path);
+    }
+}
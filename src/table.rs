@@ -1,14 +1,17 @@
 use std::collections::HashMap;
-use std::io::Read;
 
 use bigdecimal::BigDecimal;
 use bigdecimal::num_bigint::BigInt;
 use bytebuffer::ByteBuffer;
-use chrono::{DateTime, TimeZone, Utc};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
 
 use crate::encode::{*};
-use crate::response::ResponseStatus::Success;
+use crate::response::ResponseStatus::{ConnectionLost, ConnectionTimeout, Success};
+use crate::geography::{GeographyPoint, is_null_point};
 use crate::response::VoltResponseInfo;
+use crate::column_value::ColumnValue;
+use crate::row::{integer_value, Row, Rows, typed_column_index};
 
 const MIN_INT8: i8 = -1 << 7;
 
@@ -18,6 +21,62 @@ pub struct Column {
     pub header_type: i8,
 }
 
+/// Rows of a table packed into one buffer , instead of one allocation per row.
+#[derive(Debug, Default)]
+pub(crate) struct RowBuf {
+    data: Vec<u8>,
+    // start and end of every row in `data`
+    bounds: Vec<(usize, usize)>,
+}
+
+impl RowBuf {
+    fn push(&mut self, row: &[u8]) {
+        let start = self.data.len();
+        self.data.extend_from_slice(row);
+        self.bounds.push((start, self.data.len()));
+    }
+
+    pub(crate) fn get(&self, i: usize) -> Option<&[u8]> {
+        let (start, end) = *self.bounds.get(i)?;
+        Some(&self.data[start..end])
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item=&[u8]> {
+        self.bounds.iter().map(|(start, end)| &self.data[*start..*end])
+    }
+
+    /// Read `row_count` length prefixed rows , copying them with one allocation.
+    fn read(bytebuffer: &mut ByteBuffer, row_count: i32) -> Result<RowBuf, VoltError> {
+        let bytes = bytebuffer.as_bytes();
+        let section = bytebuffer.get_rpos();
+        let mut bounds = Vec::with_capacity(row_count.max(0) as usize);
+        let mut pos = section;
+        for _ in 0..row_count {
+            let prefix = bytes.get(pos..pos + 4).ok_or_else(|| VoltError::Io(std::io::ErrorKind::UnexpectedEof.into()))?;
+            let row_len = i32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]);
+            if row_len < 0 {
+                return Err(VoltError::NoValue(format!("row of length {}", row_len)));
+            }
+            let start = pos + 4 - section;
+            pos += 4 + row_len as usize;
+            if pos > bytes.len() {
+                return Err(VoltError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+            bounds.push((start, pos - section));
+        }
+        let data = bytes[section..pos].to_vec();
+        bytebuffer.set_rpos(pos);
+        Ok(RowBuf { data, bounds })
+    }
+}
+
+/// Rows are kept as they came over the wire , packed into one buffer , and a column is decoded only when it is read.
+/// Parsing a response copies its rows once , so it briefly needs about twice its size and then its size plus
+/// 16 bytes per row , but no per row allocation and no decoded copy of values that are never read.
 #[derive(Debug)]
 pub struct VoltTable {
     info: VoltResponseInfo,
@@ -26,12 +85,14 @@ pub struct VoltTable {
     column_info_bytes: ByteBuffer,
     columns: Vec<Column>,
     num_rows: i32,
-    rows: Vec<ByteBuffer>,
+    rows: RowBuf,
     row_index: i32,
     cn_to_ci: HashMap<String, i16>,
     column_offsets: Vec<i32>,
     header_size: i32,
     total_size: i32,
+    // tables after the first one of the same response
+    extra_tables: Vec<VoltTable>,
 }
 
 impl Value for VoltTable {
@@ -48,9 +109,8 @@ impl Value for VoltTable {
         bytebuffer.write_u32(self.num_rows as u32);
         self.rows.iter().for_each(|f| {
             bytebuffer.write_u32(f.len() as u32);
-            bytebuffer.write_bytes(&*f.as_bytes());
+            bytebuffer.write_bytes(f);
         });
-        println!("{}", bytebuffer.len())
     }
 
     fn marshal_in_table(&self, _bytebuffer: &mut ByteBuffer, _column_type: i8) {
@@ -97,6 +157,7 @@ impl VoltTable {
         columns.iter().for_each(|f| column_info_bytes.write_string(f.header_name.as_str()));
         let header_size = (1 + column_info_bytes.len()) as i32;
         let total_size = header_size + 8;
+        let cn_to_ci = columns.iter().enumerate().map(|(i, f)| (f.header_name.to_uppercase(), i as i16)).collect();
         //
         return VoltTable {
             info: Default::default(),
@@ -105,23 +166,25 @@ impl VoltTable {
             column_info_bytes,
             columns,
             num_rows: 0,
-            rows: vec![],
+            rows: Default::default(),
             row_index: 0,
-            cn_to_ci: Default::default(),
+            cn_to_ci,
             column_offsets: vec![],
             header_size,
             total_size,
+            extra_tables: vec![],
         };
     }
 
     pub fn add_row(&mut self, row: Vec<&dyn Value>) -> Result<i16, VoltError> {
+        crate::procedure_invocation::check_params(&row)?;
         let mut bf: ByteBuffer = ByteBuffer::new();
         self.columns.iter().enumerate().for_each(|(f, v)| {
             let da = *row.get(f).unwrap();
             da.marshal_in_table(&mut bf, v.header_type);
         });
         let len = bf.len();
-        self.rows.push(bf);
+        self.rows.push(bf.as_bytes());
         self.num_rows = self.num_rows + 1;
         self.total_size = self.total_size + (len + 4) as i32;
         return Ok(1);
@@ -157,11 +220,59 @@ impl VoltTable {
     }
 
 
+    /// Status and messages the server sent along with this table.
+    pub fn response_info(&self) -> &VoltResponseInfo {
+        &self.info
+    }
+
+    /// Milliseconds the call took inside the cluster , see `VoltResponseInfo::cluster_round_trip_time`.
+    pub fn cluster_round_trip_time(&self) -> i32 {
+        self.info.cluster_round_trip_time()
+    }
+
     pub fn has_error(&mut self) -> Option<VoltError> {
-        if self.info.get_status() == Success {
-            return Option::None;
+        return match self.info.get_status() {
+            Success => Option::None,
+            // only ever generated on the client side
+            ConnectionTimeout => Option::Some(VoltError::Timeout),
+            ConnectionLost => Option::Some(VoltError::ConnectionReset),
+            _ => Option::Some(VoltError::ExecuteFail(self.info.clone())),
+        };
+    }
+
+    /// Value of column `col` in row `row` , counted from 0 , without moving the cursor.
+    /// The row is found right away through the row index built when the table was decoded ,
+    /// but the columns in front of `col` are walked on every call to find where it starts.
+    /// Reading whole rows is cheaper with `rows` , which walks each row once.
+    pub fn value_at(&self, row: usize, col: usize) -> Result<ColumnValue, VoltError> {
+        let bytes = self.rows.get(row).ok_or_else(|| VoltError::NoValue(format!("row {}", row)))?;
+        let col = i16::try_from(col)?;
+        Row::new(&self.columns, &self.cn_to_ci, bytes)?.get_value(col)
+    }
+
+    /// The current row as a map from column name to value , for code that does not know the schema.
+    pub fn row_as_map(&self) -> Result<HashMap<String, ColumnValue>, VoltError> {
+        let bytes = usize::try_from(self.row_index).ok().and_then(|row| self.rows.get(row))
+            .ok_or_else(|| VoltError::NoValue(format!("row {}", self.row_index)))?;
+        Row::new(&self.columns, &self.cn_to_ci, bytes)?.to_map()
+    }
+
+    /// Iterate the rows after the current one , moving the cursor along like `advance_row`.
+    pub fn rows(&mut self) -> Rows<'_> {
+        Rows {
+            columns: &self.columns,
+            cn_to_ci: &self.cn_to_ci,
+            rows: &self.rows,
+            row_index: &mut self.row_index,
+            column_offsets: &mut self.column_offsets,
         }
-        return Option::Some(VoltError::ExecuteFail(self.info.clone()));
+    }
+
+    /// Run `f` on every row from the first one and collect what it returns , stopping at the first error.
+    /// Like `deserialize_rows` it leaves the cursor after the last row.
+    pub fn map_rows<T, F>(&mut self, mut f: F) -> Result<Vec<T>, VoltError> where F: FnMut(&Row) -> Result<T, VoltError> {
+        self.advance_to_row(-1);
+        self.rows().map(|row| f(&row?)).collect()
     }
 
     pub fn advance_row(&mut self) -> bool {
@@ -172,6 +283,20 @@ impl VoltTable {
         self.columns.clone()
     }
 
+    pub fn column_count(&self) -> i16 {
+        self.columns.len() as i16
+    }
+
+    /// Name of the `column`-th column as the server sent it.
+    pub fn column_name(&self, column: i16) -> Result<&str, VoltError> {
+        let v = self.columns.get(column as usize).ok_or_else(|| VoltError::NoValue(column.to_string()))?;
+        Ok(&v.header_name)
+    }
+
+    pub fn column_type(&self, column: i16) -> Result<ColumnType, VoltError> {
+        Ok(ColumnType::from(self.get_column_type_by_idx(column)?))
+    }
+
     pub fn col_length(r: &mut ByteBuffer, offset: i32, col_type: i8) -> Result<i32, VoltError> {
         match col_type {
             crate::encode::ARRAY_COLUMN => {
@@ -211,7 +336,11 @@ impl VoltTable {
             crate::encode::DECIMAL_COLUMN => {
                 return Ok(16);
             }
-            crate::encode::VAR_BIN_COLUMN => {
+            crate::encode::GEOGRAPHY_POINT_COLUMN => {
+                return Ok(16);
+            }
+            // a polygon comes as its length prefixed WKB
+            crate::encode::VAR_BIN_COLUMN | crate::encode::GEOGRAPHY_COLUMN => {
                 r.set_rpos(offset as usize);
                 let str_len = r.read_i32()?;
                 if str_len == -1 { // encoding for null string.
@@ -225,16 +354,16 @@ impl VoltTable {
 
     fn calc_offsets(&mut self) -> Result<(), VoltError> {
         let mut offsets = Vec::with_capacity((self.column_count + 1) as usize);
-        let reader = self.rows.get_mut(self.row_index as usize).ok_or(VoltError::NoValue(self.row_index.to_string()))?;
+        let row = self.rows.get(self.row_index as usize).ok_or(VoltError::NoValue(self.row_index.to_string()))?;
+        let mut reader = ByteBuffer::from_bytes(row);
         offsets.push(0);
         let mut offset = 0;
         for i in 0..self.column_count {
             let column = self.columns.get(i as usize).ok_or(VoltError::NoValue(i.to_string()))?;
-            let length = crate::table::VoltTable::col_length(reader, offset, column.header_type)?;
+            let length = crate::table::VoltTable::col_length(&mut reader, offset, column.header_type)?;
             offset = offset + length;
             offsets.push(offset);
         }
-        reader.set_rpos(0);
         self.column_offsets = offsets;
         return Ok({});
     }
@@ -312,23 +441,30 @@ impl VoltTable {
                 if bs == NULL_TIMESTAMP {
                     return Ok(None);
                 }
+                #[cfg(feature = "chrono")]
                 let res = DateTime::from_bytes(bs, column)?;
+                #[cfg(not(feature = "chrono"))]
+                let res = i64::from_bytes(bs, column)?;
                 return Ok(Some(Box::new(res)));
             }
             crate::encode::DECIMAL_COLUMN => {
                 if bs == NULL_DECIMAL {
                     return Ok(None);
                 }
-                let res = DateTime::from_bytes(bs, column)?;
+                let res = BigDecimal::from_bytes(bs, column)?;
                 return Ok(Some(Box::new(res)));
             }
             crate::encode::VAR_BIN_COLUMN => {
                 if bs.len() == 4 {
                     return Ok(None);
                 }
-                let res = Vec::from_bytes(bs, column)?;
+                let res = Vec::<u8>::from_bytes(bs, column)?;
                 return Ok(Some(Box::new(res)));
             }
+            crate::encode::GEOGRAPHY_POINT_COLUMN => {
+                let res = Option::<GeographyPoint>::from_bytes(bs, column)?;
+                return Ok(res.map(|p| Box::new(p) as Box<dyn Value>));
+            }
             _ => {
                 let res = i16::from_bytes(bs, column)?;
                 // match res {
@@ -352,6 +488,32 @@ impl VoltTable {
         return self.get_value_by_idx_type(column, tp);
     }
 
+    /// Whether column `column` of the current row holds the NULL sentinel of its type.
+    /// The `get_*` getters already return `None` for it , this is for code reading values as `take::<T>` without an `Option`.
+    pub fn is_null(&mut self, column: i16) -> Result<bool, VoltError> {
+        let tp = self.get_column_type_by_idx(column)?;
+        let bs = self.get_bytes_by_idx(column)?;
+        let null: &[u8] = match tp {
+            NULL_COLUMN => return Ok(true),
+            TINYINT_COLUMN => &NULL_BIT_VALUE,
+            SHORT_COLUMN => &NULL_SHORT_VALUE,
+            INT_COLUMN => &NULL_INT_VALUE,
+            LONG_COLUMN => &NULL_LONG_VALUE,
+            FLOAT_COLUMN => &NULL_FLOAT_VALUE,
+            TIMESTAMP_COLUMN => &NULL_TIMESTAMP,
+            DECIMAL_COLUMN => &NULL_DECIMAL,
+            STRING_COLUMN | VAR_BIN_COLUMN => &NULL_VARCHAR,
+            GEOGRAPHY_POINT_COLUMN => return Ok(is_null_point(&bs)),
+            _ => return Err(VoltError::InvalidColumnType(tp)),
+        };
+        Ok(bs == null)
+    }
+
+    pub fn is_null_by_column(&mut self, column: &str) -> Result<bool, VoltError> {
+        let idx = self.get_column_index(column)?;
+        self.is_null(idx)
+    }
+
     pub fn get_bool_by_column(&mut self, column: &str) -> Result<Option<bool>, VoltError> {
         let idx = self.get_column_index(column)?;
         return Ok(self.get_bool_by_idx(idx)?);
@@ -382,6 +544,15 @@ impl VoltTable {
         return Ok(Option::Some(bs));
     }
 
+    /// Content of the VARBINARY column `column` , an empty value is an empty `Vec` and NULL is `VoltError::UnexpectedNull`.
+    /// `get_bytes_op_by_idx` returns NULL as `None` instead.
+    pub fn get_bytes(&mut self, column: i16) -> Result<Vec<u8>, VoltError> {
+        match self.get_bytes_op_by_idx(column)? {
+            Some(bs) => Ok(bs),
+            None => Err(VoltError::UnexpectedNull(self.get_column_by_index(column)?.header_name.clone())),
+        }
+    }
+
     pub fn get_bytes_by_column(&mut self, column: &str) -> Result<Vec<u8>, VoltError> {
         let idx = self.get_column_index(column)?;
         return Ok(self.get_bytes_by_idx(idx)?);
@@ -402,6 +573,21 @@ impl VoltTable {
         return Ok(Some(decimal));
     }
 
+    /// DECIMAL column as a `rust_decimal::Decimal` , failing for values with more than 28 significant digits.
+    #[cfg(feature = "rust_decimal")]
+    pub fn get_decimal(&mut self, column: i16) -> Result<Option<rust_decimal::Decimal>, VoltError> {
+        let bs = self.get_bytes_by_idx(column)?;
+        let table_column = self.get_column_by_index(column)?;
+        Option::from_bytes(bs, table_column)
+    }
+
+    /// GEOGRAPHY_POINT column of the current row.
+    pub fn get_point(&mut self, column: i16) -> Result<Option<GeographyPoint>, VoltError> {
+        let bs = self.get_bytes_by_idx(column)?;
+        let table_column = self.get_column_by_index(column)?;
+        Option::from_bytes(bs, table_column)
+    }
+
     pub fn get_string_by_column(&mut self, column: &str) -> Result<Option<String>, VoltError> {
         let idx = self.get_column_index(column)?;
         return Ok(self.get_string_by_idx(idx)?);
@@ -444,32 +630,142 @@ impl VoltTable {
         };
     }
 
+    #[cfg(feature = "chrono")]
     pub fn get_time_by_column(&mut self, column: &str) -> Result<Option<DateTime<Utc>>, VoltError> {
         let idx = self.get_column_index(column)?;
         return Ok(self.get_time_by_idx(idx)?);
     }
 
+    #[cfg(feature = "chrono")]
     pub fn get_time_by_idx(&mut self, column: i16) -> Result<Option<DateTime<Utc>>, VoltError> {
+        self.get_timestamp_micros_by_idx(column)?.map(micros_to_time).transpose()
+    }
+
+    /// TIMESTAMP column as microseconds since the epoch , the way the server sends it.
+    pub fn get_timestamp_micros_by_idx(&mut self, column: i16) -> Result<Option<i64>, VoltError> {
         let bs = self.get_bytes_by_idx(column)?;
         if bs == NULL_TIMESTAMP {
             return Ok(Option::None);
         }
         let mut buffer = ByteBuffer::from_bytes(&bs);
-        let time = buffer.read_i64()?;
-        return Ok(Option::Some(Utc.timestamp_millis(time / 1000)));
+        Ok(Option::Some(buffer.read_i64()?))
+    }
+
+    /// TIMESTAMP column which must not be NULL , failing with `VoltError::UnexpectedNull` when it is.
+    #[cfg(feature = "chrono")]
+    pub fn get_timestamp(&mut self, column: i16) -> Result<DateTime<Utc>, VoltError> {
+        self.get_time_by_idx(column)?.ok_or_else(|| VoltError::UnexpectedNull(column.to_string()))
+    }
+
+    /// Index of the column named `column` , which must be of type `tp`.
+    fn get_typed_column_index(&self, column: &str, tp: i8) -> Result<i16, VoltError> {
+        typed_column_index(&self.columns, &self.cn_to_ci, column, tp)
+    }
+
+    /// Value of the TINYINT column `column` in the current row.
+    pub fn get_i8_by_name(&mut self, column: &str) -> Result<Option<i8>, VoltError> {
+        let idx = self.get_typed_column_index(column, TINYINT_COLUMN)?;
+        self.get_i8_by_idx(idx)
+    }
+
+    /// Value of the SMALLINT column `column` in the current row.
+    pub fn get_i16_by_name(&mut self, column: &str) -> Result<Option<i16>, VoltError> {
+        let idx = self.get_typed_column_index(column, SHORT_COLUMN)?;
+        self.get_i16_by_idx(idx)
+    }
+
+    /// Value of the INTEGER column `column` in the current row.
+    pub fn get_i32_by_name(&mut self, column: &str) -> Result<Option<i32>, VoltError> {
+        let idx = self.get_typed_column_index(column, INT_COLUMN)?;
+        self.get_i32_by_idx(idx)
+    }
+
+    /// Value of the BIGINT column `column` in the current row.
+    pub fn get_i64_by_name(&mut self, column: &str) -> Result<Option<i64>, VoltError> {
+        let idx = self.get_typed_column_index(column, LONG_COLUMN)?;
+        self.get_i64_by_idx(idx)
+    }
+
+    /// Value of the TINYINT column at index `column` in the current row.
+    /// Wider columns fail with `VoltError::TypeMismatch` , see `get_i64`.
+    pub fn get_i8(&mut self, column: i16) -> Result<Option<i8>, VoltError> {
+        self.get_integer(column)
+    }
+
+    /// Value of the TINYINT or SMALLINT column at index `column` in the current row.
+    pub fn get_i16(&mut self, column: i16) -> Result<Option<i16>, VoltError> {
+        self.get_integer(column)
+    }
+
+    /// Value of the TINYINT , SMALLINT or INTEGER column at index `column` in the current row.
+    pub fn get_i32(&mut self, column: i16) -> Result<Option<i32>, VoltError> {
+        self.get_integer(column)
+    }
+
+    /// Value of any integer column at index `column` in the current row , widened to an `i64`.
+    /// Unlike `get_i64_by_idx` the column type is checked , a column of another type fails with `VoltError::TypeMismatch`.
+    pub fn get_i64(&mut self, column: i16) -> Result<Option<i64>, VoltError> {
+        self.get_integer(column)
+    }
+
+    fn get_integer<T: TryFrom<i64>>(&mut self, column: i16) -> Result<Option<T>, VoltError> {
+        let bs = self.get_bytes_by_idx(column)?;
+        let table_column = self.columns.get(column as usize).ok_or_else(|| VoltError::NoValue(column.to_string()))?;
+        integer_value(table_column, &bs)
     }
 
+    /// Value of the FLOAT column `column` in the current row , narrowed to an `f32`.
+    pub fn get_f32_by_name(&mut self, column: &str) -> Result<Option<f32>, VoltError> {
+        let idx = self.get_typed_column_index(column, FLOAT_COLUMN)?;
+        self.get_f32_by_idx(idx)
+    }
+
+    /// Value of the FLOAT column `column` in the current row.
+    pub fn get_f64_by_name(&mut self, column: &str) -> Result<Option<f64>, VoltError> {
+        let idx = self.get_typed_column_index(column, FLOAT_COLUMN)?;
+        self.get_f64_by_idx(idx)
+    }
+
+    /// Value of the TINYINT column `column` in the current row , anything but 0 is true.
+    pub fn get_bool_by_name(&mut self, column: &str) -> Result<Option<bool>, VoltError> {
+        let idx = self.get_typed_column_index(column, TINYINT_COLUMN)?;
+        self.get_bool_by_idx(idx)
+    }
+
+    /// Value of the VARCHAR column `column` in the current row.
+    pub fn get_string_by_name(&mut self, column: &str) -> Result<Option<String>, VoltError> {
+        let idx = self.get_typed_column_index(column, STRING_COLUMN)?;
+        self.get_string_by_idx(idx)
+    }
+
+    /// Value of the DECIMAL column `column` in the current row.
+    pub fn get_decimal_by_name(&mut self, column: &str) -> Result<Option<BigDecimal>, VoltError> {
+        let idx = self.get_typed_column_index(column, DECIMAL_COLUMN)?;
+        self.get_decimal_by_idx(idx)
+    }
+
+    /// Value of the TIMESTAMP column `column` in the current row.
+    #[cfg(feature = "chrono")]
+    pub fn get_time_by_name(&mut self, column: &str) -> Result<Option<DateTime<Utc>>, VoltError> {
+        let idx = self.get_typed_column_index(column, TIMESTAMP_COLUMN)?;
+        self.get_time_by_idx(idx)
+    }
+
+    /// Value of the VARBINARY column `column` in the current row.
+    pub fn get_bytes_by_name(&mut self, column: &str) -> Result<Option<Vec<u8>>, VoltError> {
+        let idx = self.get_typed_column_index(column, VAR_BIN_COLUMN)?;
+        self.get_bytes_op_by_idx(idx)
+    }
 
     pub fn get_bytes_by_idx(&mut self, column_index: i16) -> Result<Vec<u8>, VoltError> {
         if self.column_offsets.len() == 0 {
             self.calc_offsets()?;
         }
-        let buffer = self.rows.get_mut(self.row_index as usize).ok_or(VoltError::NoValue(self.row_index.to_string()))?;
-        let start = self.column_offsets.get(column_index as usize).ok_or(VoltError::NoValue(column_index.to_string()))?;
-        let end = self.column_offsets.get(column_index as usize + 1).ok_or(VoltError::NoValue(column_index.to_string()))?;
-        buffer.set_rpos(*start as usize);
-        let rsize = (*end - *start) as usize;
-        Ok(buffer.read_bytes(rsize)?)
+        let row = self.rows.get(self.row_index as usize).ok_or(VoltError::NoValue(self.row_index.to_string()))?;
+        let start = *self.column_offsets.get(column_index as usize).ok_or(VoltError::NoValue(column_index.to_string()))? as usize;
+        let end = *self.column_offsets.get(column_index as usize + 1).ok_or(VoltError::NoValue(column_index.to_string()))? as usize;
+        let bs = row.get(start..end).ok_or_else(|| VoltError::Io(std::io::ErrorKind::UnexpectedEof.into()))?;
+        Ok(bs.to_vec())
     }
 
     pub fn advance_to_row(&mut self, row_index: i32) -> bool {
@@ -481,6 +777,49 @@ impl VoltTable {
         return true;
     }
 
+    /// The `i`-th table after this one in the same response.
+    pub(crate) fn extra_table(&mut self, i: usize) -> Option<&mut VoltTable> {
+        self.extra_tables.get_mut(i)
+    }
+
+    /// Number of rows in the table , whatever the cursor is at.
+    pub fn row_count(&self) -> i64 {
+        self.rows.len() as i64
+    }
+
+    /// Rows touched by an INSERT , UPDATE or DELETE, which return a single BIGINT row counting them instead of rows.
+    /// Fails with `VoltError::NoValue` when this is not such a table.
+    pub fn modified_rows(&self) -> Result<i64, VoltError> {
+        let count = match (self.columns.as_slice(), self.rows.len()) {
+            ([column], 1) if column.header_type == LONG_COLUMN => self.rows.get(0),
+            _ => None,
+        };
+        let count = count.ok_or_else(|| VoltError::NoValue("modified_tuples".to_owned()))?;
+        Ok(ByteBuffer::from_bytes(count).read_i64()?)
+    }
+
+    /// Number of tables in the response , this one included. A procedure returns one per statement it selected with.
+    pub fn table_count(&self) -> usize {
+        1 + self.extra_tables.len()
+    }
+
+    /// Take out the next table of the response , `None` once this is the last one.
+    pub fn next_table(&mut self) -> Option<VoltTable> {
+        if self.extra_tables.is_empty() {
+            return None;
+        }
+        Some(self.extra_tables.remove(0))
+    }
+
+    /// All tables of the response in order , starting with this one.
+    pub fn into_tables(mut self) -> Vec<VoltTable> {
+        let mut tables = Vec::with_capacity(self.table_count());
+        let extra = std::mem::take(&mut self.extra_tables);
+        tables.push(self);
+        tables.extend(extra);
+        tables
+    }
+
     fn get_column_type_by_idx(&self, column_idx: i16) -> Result<i8, VoltError> {
         let v = self.columns.get(column_idx as usize);
         if v.is_some() {
@@ -500,15 +839,25 @@ pub fn new_volt_table(bytebuffer: &mut ByteBuffer, info: VoltResponseInfo) -> Re
             column_info_bytes: Default::default(),
             columns: vec![],
             num_rows: -1,
-            rows: vec![],
+            rows: Default::default(),
             row_index: -1,
             cn_to_ci: Default::default(),
             column_offsets: vec![],
             header_size: 0,
             total_size: 0,
+            extra_tables: vec![],
         });
     }
 
+    let mut table = decode_table(bytebuffer, info.clone())?;
+    for _ in 1..info.get_rows_number() {
+        let extra = decode_table(bytebuffer, info.clone())?;
+        table.extra_tables.push(extra);
+    }
+    Ok(table)
+}
+
+fn decode_table(bytebuffer: &mut ByteBuffer, info: VoltResponseInfo) -> Result<VoltTable, VoltError> {
     let column_counts = decode_table_common(bytebuffer)?;
     let mut column_types: Vec<i8> = Vec::with_capacity(column_counts as usize);
     let mut column_info_bytes = ByteBuffer::new();
@@ -529,14 +878,7 @@ pub fn new_volt_table(bytebuffer: &mut ByteBuffer, info: VoltResponseInfo) -> Re
         cn_to_ci.insert(name, i);
     }
     let row_count = bytebuffer.read_i32()?;
-    let mut rows: Vec<ByteBuffer> = Vec::with_capacity(column_counts as usize);
-    for _ in 0..row_count {
-        let row_len = bytebuffer.read_i32()?;
-        let mut build = vec![0; row_len as usize];
-        bytebuffer.read_exact(&mut build)?;
-        let row = ByteBuffer::from_bytes(&*build);
-        rows.push(row);
-    }
+    let rows = RowBuf::read(bytebuffer, row_count)?;
     return Ok(VoltTable {
         info,
         column_count: column_counts,
@@ -550,6 +892,7 @@ pub fn new_volt_table(bytebuffer: &mut ByteBuffer, info: VoltResponseInfo) -> Re
         column_offsets: vec![],
         header_size: 0,
         total_size: 0,
+        extra_tables: vec![],
     });
 }
 
@@ -665,7 +1008,7 @@ mod tests {
         let i6 = table.get_decimal_by_idx(5).unwrap();
         let i7 = table.get_string_by_idx(6).unwrap();
         let i8 = table.get_bytes_op_by_idx(7).unwrap();
-        let i9 = table.get_time_by_idx(8).unwrap();
+        let i9 = table.get_timestamp_micros_by_idx(8).unwrap();
 
         assert_eq!(i1, None);
         assert_eq!(i2, None);
@@ -678,6 +1021,289 @@ mod tests {
         assert_eq!(i9, None);
         let offsets = vec![0, 1, 3, 7, 15, 23, 39, 43, 47, 55];
         assert_eq!(offsets, table.column_offsets);
+        for i in 0..9 {
+            assert!(table.is_null(i).unwrap());
+            assert!(table.get_value_by_idx(i).unwrap().is_none());
+        }
+        assert!(table.is_null_by_column("t4").unwrap());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_timestamp() {
+        let header = vec!["T".to_owned()];
+        let mut table = VoltTable::new_table(vec![TIMESTAMP_COLUMN], header);
+        let time = micros_to_time(1637323002445123).unwrap();
+        let none: Option<DateTime<Utc>> = None;
+        table.add_row(volt_param! {time}).unwrap();
+        table.add_row(volt_param! {none}).unwrap();
+        assert!(table.advance_to_row(0));
+        assert_eq!(table.get_timestamp(0).unwrap(), time);
+        assert_eq!(table.get_timestamp_micros_by_idx(0).unwrap(), Some(1637323002445123));
+        assert!(table.advance_row());
+        assert_eq!(table.get_time_by_idx(0).unwrap(), None);
+        assert!(matches!(table.get_timestamp(0), Err(VoltError::UnexpectedNull(_))));
+    }
+
+    #[test]
+    fn test_get_bytes() {
+        let header = vec!["B".to_owned()];
+        let mut table = VoltTable::new_table(vec![VAR_BIN_COLUMN], header);
+        let (data, empty, none): (Option<Vec<u8>>, Vec<u8>, Option<Vec<u8>>) = (Some(vec![1, 2]), vec![], None);
+        table.add_row(volt_param! {data}).unwrap();
+        table.add_row(volt_param! {empty}).unwrap();
+        table.add_row(volt_param! {none}).unwrap();
+        assert!(table.advance_to_row(0));
+        assert_eq!(table.get_bytes(0).unwrap(), vec![1, 2]);
+        assert!(table.advance_row());
+        assert_eq!(table.get_bytes(0).unwrap(), Vec::<u8>::new());
+        assert_eq!(table.get_bytes_op_by_idx(0).unwrap(), Some(vec![]));
+        assert!(table.advance_row());
+        assert!(matches!(table.get_bytes(0), Err(VoltError::UnexpectedNull(column)) if column == "B"));
+        assert_eq!(table.get_bytes_op_by_idx(0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_point() {
+        let header = vec!["ID".to_owned(), "P".to_owned()];
+        let mut table = VoltTable::new_table(vec![INT_COLUMN, GEOGRAPHY_POINT_COLUMN], header);
+        let (point, none) = (GeographyPoint::new(4.89, 52.37), None::<GeographyPoint>);
+        table.add_row(volt_param! {1, point}).unwrap();
+        table.add_row(volt_param! {2, none}).unwrap();
+        assert!(table.advance_to_row(0));
+        assert_eq!(table.get_point(1).unwrap(), Some(point));
+        assert!(!table.is_null(1).unwrap());
+        assert!(table.advance_row());
+        assert_eq!(table.get_i32_by_idx(0).unwrap(), Some(2));
+        assert_eq!(table.get_point(1).unwrap(), None);
+        assert!(table.is_null(1).unwrap());
+    }
+
+    #[test]
+    fn test_is_null() {
+        let header = vec!["A".to_owned(), "B".to_owned(), "C".to_owned()];
+        let mut table = VoltTable::new_table(vec![LONG_COLUMN, DECIMAL_COLUMN, STRING_COLUMN], header);
+        let (long, decimal) = (i64::MIN + 1, BigDecimal::from(-3));
+        let (no_long, no_decimal, no_string): (Option<i64>, Option<BigDecimal>, Option<String>) = (None, None, None);
+        table.add_row(volt_param! {long, decimal, ""}).unwrap();
+        table.add_row(volt_param! {no_long, no_decimal, no_string}).unwrap();
+        assert!(table.advance_to_row(0));
+        for i in 0..3 {
+            assert!(!table.is_null(i).unwrap());
+        }
+        assert_eq!(table.get_i64_by_idx(0).unwrap(), Some(i64::MIN + 1));
+        let decimal = table.get_value_by_idx(1).unwrap().unwrap();
+        assert_eq!(decimal.to_value_string(), BigDecimal::from(-3).with_scale(12).to_string());
+        assert!(table.advance_row());
+        for i in 0..3 {
+            assert!(table.is_null(i).unwrap());
+        }
+        assert_eq!(table.get_i64_by_idx(0).unwrap(), None);
+        assert_eq!(table.get_decimal_by_idx(1).unwrap(), None);
+        assert_eq!(table.get_string_by_idx(2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_rows() {
+        let header = vec!["ID".to_owned(), "NAME".to_owned()];
+        let mut table = VoltTable::new_table(vec![LONG_COLUMN, STRING_COLUMN], header);
+        table.add_row(volt_param! {1 as i64, "one"}).unwrap();
+        table.add_row(volt_param! {2 as i64, Option::<String>::None}).unwrap();
+        table.add_row(volt_param! {3 as i64, "three"}).unwrap();
+        let mut b = ByteBuffer::new();
+        b.write_u8(0);
+        b.write_i8(1);
+        b.write_i8(-128);
+        b.write_i32(0);
+        b.write_i16(1);
+        table.marshal(&mut b);
+        b.set_rpos(0);
+        let info = VoltResponseInfo::new(&mut b, 1).unwrap();
+        b.read_i8().unwrap();
+        let mut table = new_volt_table(&mut b, info).unwrap();
+
+        let mut seen = Vec::new();
+        for row in table.rows() {
+            let row = row.unwrap();
+            seen.push((row.get_i64(0).unwrap().unwrap(), row.get_string_by_name("name").unwrap()));
+            assert!(matches!(row.get_i32_by_name("ID"), Err(VoltError::TypeMismatch(..))));
+        }
+        assert_eq!(seen, vec![(1, Some("one".to_owned())), (2, None), (3, Some("three".to_owned()))]);
+        // the cursor stays on the last row
+        assert_eq!(table.get_i64_by_idx(0).unwrap(), Some(3));
+        assert!(!table.advance_row());
+        assert_eq!(table.rows().count(), 0);
+        // from the first row again
+        let names = table.map_rows(|row| Ok(row.get_string_by_name("NAME")?.unwrap_or_default())).unwrap();
+        assert_eq!(names, vec!["one", "", "three"]);
+        let mut calls = 0;
+        let res = table.map_rows(|row| {
+            calls += 1;
+            row.get_string_by_name("NAME")?.ok_or_else(|| VoltError::UnexpectedNull("NAME".to_owned()))
+        });
+        assert!(matches!(res, Err(VoltError::UnexpectedNull(_))));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_get_by_name() {
+        let header = vec!["ID".to_owned(), "Name".to_owned()];
+        let mut table = VoltTable::new_table(vec![LONG_COLUMN, STRING_COLUMN], header);
+        table.add_row(volt_param! {7 as i64, "seven"}).unwrap();
+        assert!(table.advance_to_row(0));
+        assert_eq!(table.get_i64_by_name("id").unwrap(), Some(7));
+        assert_eq!(table.get_string_by_name("NAME").unwrap(), Some("seven".to_owned()));
+        assert!(matches!(table.get_i64_by_name("missing"), Err(VoltError::NoSuchColumn(c)) if c == "missing"));
+        assert!(matches!(table.get_i32_by_name("ID"), Err(VoltError::TypeMismatch(c, INT_COLUMN, LONG_COLUMN)) if c == "ID"));
+    }
+
+    #[test]
+    fn test_f32() {
+        let header = vec!["T".to_owned()];
+        let mut table = VoltTable::new_table(vec![FLOAT_COLUMN], header);
+        let (reading, none, huge) = (21.5f32, None::<f32>, f64::MAX);
+        table.add_row(volt_param! {reading}).unwrap();
+        table.add_row(volt_param! {none}).unwrap();
+        table.add_row(volt_param! {huge}).unwrap();
+        assert!(table.advance_to_row(0));
+        assert_eq!(table.get_f32_by_name("T").unwrap(), Some(21.5));
+        assert_eq!(table.get_f64_by_idx(0).unwrap(), Some(21.5));
+        assert!(table.advance_to_row(1));
+        assert_eq!(table.get_f32_by_idx(0).unwrap(), None);
+        assert!(table.advance_to_row(2));
+        assert!(matches!(table.get_f32_by_idx(0), Err(VoltError::ValueOutOfRange(_))));
+    }
+
+    #[test]
+    fn test_integer_widths() {
+        let header = vec!["T".to_owned(), "I".to_owned(), "S".to_owned()];
+        let mut table = VoltTable::new_table(vec![TINYINT_COLUMN, INT_COLUMN, STRING_COLUMN], header);
+        let (tiny, int, none) = (-3i8, 70_000i32, None::<i32>);
+        table.add_row(volt_param! {tiny, int, "a"}).unwrap();
+        table.add_row(volt_param! {tiny, none, "b"}).unwrap();
+        assert!(table.advance_to_row(0));
+        assert_eq!(table.get_i8(0).unwrap(), Some(-3));
+        assert_eq!(table.get_i64(0).unwrap(), Some(-3));
+        assert_eq!(table.get_i32(1).unwrap(), Some(70_000));
+        assert_eq!(table.get_i64(1).unwrap(), Some(70_000));
+        assert!(matches!(table.get_i8(1), Err(VoltError::TypeMismatch(c, TINYINT_COLUMN, INT_COLUMN)) if c == "I"));
+        assert!(matches!(table.get_i16(1), Err(VoltError::TypeMismatch(..))));
+        assert!(matches!(table.get_i64(2), Err(VoltError::TypeMismatch(..))));
+        {
+            let row = table.rows().next().unwrap().unwrap();
+            assert_eq!(row.get_i16(0).unwrap(), Some(-3));
+            assert!(matches!(row.get_i8(1), Err(VoltError::TypeMismatch(..))));
+        }
+        assert!(table.advance_to_row(1));
+        assert_eq!(table.get_i64(1).unwrap(), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_value_at() {
+        use chrono::TimeZone;
+
+        let header = vec!["ID".to_owned(), "NAME".to_owned(), "AT".to_owned()];
+        let mut table = VoltTable::new_table(vec![LONG_COLUMN, STRING_COLUMN, TIMESTAMP_COLUMN], header);
+        let (none, at) = (None::<String>, Utc.timestamp_millis_opt(1_500).unwrap());
+        table.add_row(volt_param! {1 as i64, "one", at}).unwrap();
+        table.add_row(volt_param! {2 as i64, none, at}).unwrap();
+        assert_eq!(table.value_at(1, 0).unwrap(), ColumnValue::BigInt(2));
+        assert_eq!(table.value_at(0, 1).unwrap(), ColumnValue::String("one".to_owned()));
+        assert_eq!(table.value_at(1, 1).unwrap(), ColumnValue::Null);
+        assert_eq!(table.value_at(0, 2).unwrap(), ColumnValue::Timestamp(1_500_000));
+        assert!(table.value_at(2, 0).is_err());
+        assert!(table.value_at(0, 3).is_err());
+
+        assert!(table.advance_to_row(1));
+        let row = table.row_as_map().unwrap();
+        assert_eq!(row.len(), 3);
+        assert_eq!(row["ID"], ColumnValue::BigInt(2));
+        assert_eq!(row["NAME"], ColumnValue::Null);
+        // before the first row
+        assert!(table.advance_to_row(-1));
+        assert!(table.row_as_map().is_err());
+    }
+
+    #[test]
+    fn test_decode_rows() {
+        let header = vec!["ID".to_owned(), "NAME".to_owned()];
+        let mut table = VoltTable::new_table(vec![LONG_COLUMN, STRING_COLUMN], header);
+        table.add_row(volt_param! {1 as i64, "one"}).unwrap();
+        table.add_row(volt_param! {2 as i64, "two"}).unwrap();
+        let mut bf = ByteBuffer::new();
+        table.marshal(&mut bf);
+        // without the type byte
+        let bs = &bf.as_bytes()[1..];
+        let mut decoded = decode_table(&mut ByteBuffer::from_bytes(bs), Default::default()).unwrap();
+        assert!(decoded.advance_row());
+        assert_eq!(decoded.get_string_by_idx(1).unwrap(), Some("one".to_owned()));
+        assert!(decoded.advance_row());
+        assert_eq!(decoded.get_i64_by_idx(0).unwrap(), Some(2));
+        assert!(!decoded.advance_row());
+        let truncated = &bs[..bs.len() - 1];
+        assert!(matches!(decode_table(&mut ByteBuffer::from_bytes(truncated), Default::default()), Err(VoltError::Io(_))));
+    }
+
+    #[test]
+    fn test_multiple_tables() {
+        let mut bf = ByteBuffer::new();
+        for id in [1 as i64, 2, 3] {
+            let mut table = VoltTable::new_table(vec![LONG_COLUMN], vec!["ID".to_owned()]);
+            table.add_row(volt_param! {id}).unwrap();
+            let mut one = ByteBuffer::new();
+            table.marshal(&mut one);
+            // without the type byte
+            bf.write_bytes(&one.as_bytes()[1..]);
+        }
+        let mut info_bytes = ByteBuffer::new();
+        info_bytes.write_u8(0);
+        info_bytes.write_i8(1);
+        info_bytes.write_i8(-128);
+        info_bytes.write_i32(0);
+        info_bytes.write_i16(3);
+        let info = VoltResponseInfo::new(&mut info_bytes, 1).unwrap();
+        let first_id = |table: &mut VoltTable| {
+            assert!(table.advance_row());
+            table.get_i64_by_idx(0).unwrap().unwrap()
+        };
+
+        let mut table = new_volt_table(&mut bf, info.clone()).unwrap();
+        assert_eq!(table.table_count(), 3);
+        assert_eq!(first_id(&mut table.next_table().unwrap()), 2);
+        assert_eq!(first_id(&mut table.next_table().unwrap()), 3);
+        assert!(table.next_table().is_none());
+        assert_eq!(first_id(&mut table), 1);
+
+        bf.set_rpos(0);
+        let tables = new_volt_table(&mut bf, info).unwrap().into_tables();
+        let ids: Vec<i64> = tables.into_iter().map(|mut t| first_id(&mut t)).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_modified_rows() {
+        let mut table = VoltTable::new_table(vec![LONG_COLUMN], vec!["modified_tuples".to_owned()]);
+        table.add_row(volt_param! {3 as i64}).unwrap();
+        assert_eq!(table.row_count(), 1);
+        assert_eq!(table.modified_rows().unwrap(), 3);
+        let mut rows = VoltTable::new_table(vec![LONG_COLUMN, STRING_COLUMN], vec!["ID".to_owned(), "NAME".to_owned()]);
+        rows.add_row(volt_param! {3 as i64, "three"}).unwrap();
+        rows.add_row(volt_param! {4 as i64, "four"}).unwrap();
+        assert_eq!(rows.row_count(), 2);
+        assert!(matches!(rows.modified_rows(), Err(VoltError::NoValue(_))));
+    }
+
+    #[test]
+    fn test_column_metadata() {
+        let header = vec!["ID".to_owned(), "Name".to_owned()];
+        let table = VoltTable::new_table(vec![LONG_COLUMN, STRING_COLUMN], header);
+        assert_eq!(table.column_count(), 2);
+        assert_eq!(table.column_name(1).unwrap(), "Name");
+        assert_eq!(table.column_type(0).unwrap(), ColumnType::BigInt);
+        assert_eq!(table.column_type(1).unwrap(), ColumnType::String);
+        assert!(matches!(table.column_name(2), Err(VoltError::NoValue(_))));
+        assert_eq!(ColumnType::from(42), ColumnType::Unknown(42));
     }
 
     #[test]
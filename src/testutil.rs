@@ -0,0 +1,241 @@
+//! Fake server for testing code built on the client without a running VoltDB , needs the `test-util` feature.
+//!
+//! ```no_run
+//! use voltdb_client_rust::{block_for_result, Node, NodeOpt, Value, VoltTable, INT_COLUMN, volt_param};
+//! use voltdb_client_rust::testutil::MockServer;
+//!
+//! let mut table = VoltTable::new_table(vec![INT_COLUMN], vec!["A".to_owned()]);
+//! table.add_row(volt_param!(1)).unwrap();
+//! let server = MockServer::builder().with_tables("@AdHoc", &[table]).start().unwrap();
+//! let node = Node::new(NodeOpt::new(server.ip_port())).unwrap();
+//! let table = block_for_result(&node.query("select a from t").unwrap()).unwrap();
+//! assert_eq!(server.calls(), vec!["@AdHoc"]);
+//! ```
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use bytebuffer::ByteBuffer;
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::encode::{INT_COLUMN, Value, VoltError};
+use crate::node::IpPort;
+use crate::table::VoltTable;
+
+/// Next frame , `None` once the client closed the connection.
+pub(crate) fn read_frame(stream: &mut TcpStream) -> Option<ByteBuffer> {
+    let len = stream.read_u32::<BigEndian>().ok()?;
+    let mut all = vec![0; len as usize];
+    stream.read_exact(&mut all).ok()?;
+    Some(ByteBuffer::from_bytes(&all))
+}
+
+/// `body` with its length in front , as every message on the wire.
+pub(crate) fn frame(body: ByteBuffer) -> Vec<u8> {
+    let mut frame = ByteBuffer::new();
+    frame.write_u32(body.len() as u32);
+    frame.write_bytes(body.as_bytes());
+    frame.into_vec()
+}
+
+/// Accepted login of host 1 , connection 2 , with the leader on localhost and the build string `test`.
+pub(crate) fn login_response() -> Vec<u8> {
+    let mut login = ByteBuffer::new();
+    login.write_u8(0);
+    login.write_u8(0);
+    login.write_i32(1);
+    login.write_i64(2);
+    // cluster start time
+    login.write_i64(1_600_000_000_000);
+    login.write_i32(0x7f000001);
+    login.write_string("test");
+    frame(login)
+}
+
+/// What follows the handle in a successful response with `tables`.
+fn tables_body(tables: &[VoltTable]) -> Vec<u8> {
+    let mut res = ByteBuffer::new();
+    res.write_u8(0);
+    res.write_i8(1);
+    res.write_i8(-128);
+    res.write_i32(0);
+    res.write_i16(tables.len() as i16);
+    for table in tables {
+        let mut bf = ByteBuffer::new();
+        table.marshal(&mut bf);
+        // without the type byte
+        res.write_bytes(&bf.as_bytes()[1..]);
+    }
+    res.into_vec()
+}
+
+/// What follows the handle in a response failing with `status` and `message`.
+fn error_body(status: i8, message: &str) -> Vec<u8> {
+    let mut res = ByteBuffer::new();
+    res.write_u8(1 << 5);
+    res.write_i8(status);
+    res.write_string(message);
+    res.write_i8(-128);
+    res.write_i32(0);
+    res.write_i16(0);
+    res.into_vec()
+}
+
+fn response(handle: i64, body: &[u8]) -> Vec<u8> {
+    let mut res = ByteBuffer::new();
+    res.write_u8(0);
+    res.write_i64(handle);
+    res.write_bytes(body);
+    frame(res)
+}
+
+/// Response to `handle` with `tables`.
+#[cfg(test)]
+pub(crate) fn tables_response(handle: i64, tables: &[VoltTable]) -> Vec<u8> {
+    response(handle, &tables_body(tables))
+}
+
+/// Response to `handle` failing with `status` and `message` , without tables.
+#[cfg(test)]
+pub(crate) fn error_response(handle: i64, status: i8, message: &str) -> Vec<u8> {
+    response(handle, &error_body(status, message))
+}
+
+/// Server on a free port of localhost , logging every client in and answering calls by procedure name.
+/// A procedure without a canned response fails like an unknown one on a real server , except `@Ping` which succeeds.
+/// Every connection is served on its own thread , until the server is dropped.
+pub struct MockServer {
+    ip_port: IpPort,
+    calls: Arc<Mutex<Vec<String>>>,
+    stop: Arc<AtomicBool>,
+}
+
+/// Canned responses of a `MockServer` , the encoded response following the handle by procedure name.
+#[derive(Debug, Clone, Default)]
+pub struct MockServerBuilder {
+    responses: HashMap<String, Vec<u8>>,
+}
+
+impl MockServer {
+    /// Collect the canned responses , then `start` the server.
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder::default()
+    }
+
+    /// Address to connect to , e.g. with `NodeOpt::new`.
+    pub fn ip_port(&self) -> IpPort {
+        self.ip_port.clone()
+    }
+
+    /// Names of the procedures called so far , over all connections in the order they arrived.
+    pub fn calls(&self) -> Vec<String> {
+        match self.calls.lock() {
+            Ok(calls) => calls.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn serve(mut stream: TcpStream, responses: &HashMap<String, Vec<u8>>, calls: &Mutex<Vec<String>>) {
+        if read_frame(&mut stream).is_none() || stream.write_all(&login_response()).is_err() {
+            return;
+        }
+        let ping = tables_body(&[VoltTable::new_table(vec![INT_COLUMN], vec!["STATUS".to_owned()])]);
+        while let Some(mut call) = read_frame(&mut stream) {
+            let (name, handle) = match (call.read_u8(), call.read_string(), call.read_i64()) {
+                (Ok(_), Ok(name), Ok(handle)) => (name, handle),
+                _ => return,
+            };
+            if let Ok(mut calls) = calls.lock() {
+                calls.push(name.clone());
+            }
+            let unknown;
+            let body = match responses.get(&name) {
+                Some(body) => body,
+                None if name == "@Ping" => &ping,
+                None => {
+                    unknown = error_body(-2, &format!("Procedure {} was not found", name));
+                    &unknown
+                }
+            };
+            if stream.write_all(&response(handle, body)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // wake the accepting thread up so it sees the flag
+        let _ = TcpStream::connect((self.ip_port.ip_host.as_str(), self.ip_port.port));
+    }
+}
+
+impl MockServerBuilder {
+    /// Answer calls of `procedure` with `tables`.
+    pub fn with_tables(mut self, procedure: &str, tables: &[VoltTable]) -> Self {
+        self.responses.insert(procedure.to_owned(), tables_body(tables));
+        self
+    }
+
+    /// Fail calls of `procedure` with `status` , e.g. -2 for a constraint violation , and `message`.
+    pub fn with_error(mut self, procedure: &str, status: i8, message: &str) -> Self {
+        self.responses.insert(procedure.to_owned(), error_body(status, message));
+        self
+    }
+
+    /// Listen on a free port of localhost.
+    pub fn start(self) -> Result<MockServer, VoltError> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let calls = Arc::new(Mutex::new(vec![]));
+        let stop = Arc::new(AtomicBool::new(false));
+        let responses = Arc::new(self.responses);
+        let (seen, stopping) = (Arc::clone(&calls), Arc::clone(&stop));
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let (responses, seen) = (Arc::clone(&responses), Arc::clone(&seen));
+                thread::spawn(move || MockServer::serve(stream, &responses, &seen));
+            }
+        });
+        Ok(MockServer { ip_port: IpPort::new("127.0.0.1".to_string(), port), calls, stop })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{block_for_result, LONG_COLUMN, Node, NodeOpt, volt_param};
+
+    use super::*;
+
+    #[test]
+    fn test_mock_server() {
+        let mut count = VoltTable::new_table(vec![LONG_COLUMN], vec!["COUNT".to_owned()]);
+        count.add_row(volt_param!(7i64)).unwrap();
+        let server = MockServer::builder()
+            .with_tables("Count", &[count])
+            .with_error("Insert", -2, "duplicate")
+            .start().unwrap();
+        let node = Node::new(NodeOpt::new(server.ip_port())).unwrap();
+        let mut table = node.call_sp_sync("Count", volt_param!()).unwrap();
+        assert!(table.advance_row());
+        assert_eq!(table.get_i64_by_idx(0).unwrap(), Some(7));
+        assert!(matches!(node.call_sp_sync("Insert", volt_param!()), Err(VoltError::ExecuteFail(_))));
+        let err = node.call_sp_sync("Nope", volt_param!()).unwrap_err();
+        assert!(err.to_string().contains("Procedure Nope was not found"));
+        node.ping().unwrap();
+        assert_eq!(server.calls(), vec!["Count", "Insert", "Nope", "@Ping"]);
+        // a second connection is served while the first is open
+        let other = Node::new(NodeOpt::new(server.ip_port())).unwrap();
+        assert!(block_for_result(&other.call_sp("Count", volt_param!()).unwrap()).is_ok());
+    }
+}
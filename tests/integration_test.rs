@@ -20,8 +20,7 @@ fn populate(node: &mut Node) {
     POPULATE.call_once(|| {
         let jars = fs::read("tests/procedures.jar").unwrap();
         let x = node.upload_jar(jars).unwrap();
-        let mut table = x.recv().unwrap();
-        assert!(table.has_error().is_none());
+        block_for_result(&x).unwrap();
 
         let create = "CREATE TABLE test_types
                     (
@@ -38,18 +37,15 @@ fn populate(node: &mut Node) {
         execute_success(node, create);
         let script = "CREATE PROCEDURE  FROM CLASS com.johnny.ApplicationCreate;";
         let x = node.query(script).unwrap();
-        let mut table = x.recv().unwrap();
-        assert!(table.has_error().is_none());
+        block_for_result(&x).unwrap();
     });
 }
 
 
 fn execute_success(node: &mut Node, sql: &str) {
     let x = node.query(sql).unwrap();
-    let mut table = x.recv().unwrap();
-    let err = table.has_error();
-    if err.is_some() {
-        panic!("err {:?} ", err.unwrap())
+    if let Err(err) = block_for_result(&x) {
+        panic!("err {:?} ", err)
     }
 }
 
@@ -109,8 +105,7 @@ fn test_multiples_thread() -> Result<(), VoltError> {
     let insert = "insert into test_types (T1) values (NULL);";
     execute_success(&mut node, insert);
     let x = node.query("insert into test_types (T1,T2,T3,T4,T5,T6,T7,T8,T9) values (1,2,3,4,5,6,'7','8',NOW());").unwrap();
-    let mut table = x.recv().unwrap();
-    assert!(table.has_error().is_some());
+    assert!(matches!(block_for_result(&x), Err(VoltError::ExecuteFail(_))));
 
 
     let insert_value = "insert into test_types (T1,T2,T3,T4,T5,T6,T7,T8,T9) values (1,2,3,4,5,6,'7','089CD7B35220FFB686012A0B08B49ECD8C06109893971F422F4D4F4E49544F52494E475F33393766643034662D656161642D346230372D613638302D62663562633736666132363148D535A8019CD7B352B001DDEE8501B801BAEE8501C001AAE98601CA01054341534831D0010AE00102E80102F20103555344FA010A0A0355534410809BEE028202050A035553448A020B08B49ECD8C06109893971F9202046E756C6CA2020A0A0355534410C0BD9A2FBA0219312C323139313936382C323139333231302C32313933323435C802C91E8A0400920400D80401880505B20500',NOW());";
@@ -0,0 +1,35 @@
+//! Derive macros re-exported by `voltdb-client-rust` with its `derive` feature.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Index, parse_macro_input};
+
+/// Implement `VoltParams` , the fields become the procedure parameters in declaration order.
+#[proc_macro_derive(VoltParams)]
+pub fn derive_volt_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Error::new_spanned(&input.ident, "VoltParams can only be derived for structs").to_compile_error().into(),
+    };
+    let params: Vec<_> = match fields {
+        Fields::Named(named) => named.named.iter().map(|f| {
+            let name = &f.ident;
+            quote! { &self.#name as &dyn ::voltdb_client_rust::Value }
+        }).collect(),
+        Fields::Unnamed(unnamed) => (0..unnamed.unnamed.len()).map(|i| {
+            let idx = Index::from(i);
+            quote! { &self.#idx as &dyn ::voltdb_client_rust::Value }
+        }).collect(),
+        Fields::Unit => vec![],
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::voltdb_client_rust::VoltParams for #ident #ty_generics #where_clause {
+            fn volt_params(&self) -> ::std::vec::Vec<&dyn ::voltdb_client_rust::Value> {
+                ::std::vec![#(#params),*]
+            }
+        }
+    }.into()
+}